@@ -0,0 +1,74 @@
+//! Compare the broadphase grid against checking every pair of players, for increasingly large
+//! numbers of players. Run with `cargo bench --bench broadphase`.
+
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use punchball::player::collision::{self, Body, BodyId, Contact};
+use rand::Rng;
+
+/// How far something moving at a velocity of 1.0 travels in one frame at 60 frames per second
+const STEP: f32 = 25.0 / 60.0;
+/// How many frames' worth of collision detection to time for each player count
+const FRAMES: u32 = 100;
+
+/// Players scattered at random, with the playing area growing with the player count so the
+/// crowd stays about as dense as a four player game
+fn random_bodies(count: usize) -> Vec<Body> {
+    let mut rng = rand::thread_rng();
+    let half_size = 96.0 * (count as f32).sqrt();
+    (0..count)
+        .map(|player_id| Body {
+            id: BodyId::Player(player_id),
+            pos: Vec2::new(
+                rng.gen_range(-half_size..half_size),
+                rng.gen_range(-half_size..half_size),
+            ),
+            vel: Vec2::new(rng.gen_range(-6.0..6.0), rng.gen_range(-6.0..6.0)),
+            radius: 32.0,
+            inv_mass: 1.0,
+            restitution: 0.9,
+        })
+        .collect()
+}
+
+/// The old way: test every pair
+fn brute_force_contacts(bodies: &[Body]) -> Vec<Contact> {
+    let mut contacts = Vec::new();
+    for a in 0..bodies.len() {
+        for b in a + 1..bodies.len() {
+            contacts.extend(Contact::between(bodies, a, b, STEP));
+        }
+    }
+    contacts
+}
+
+/// Average time per frame to find the contacts, and how many contacts were found
+fn time(bodies: &[Body], find: impl Fn(&[Body]) -> Vec<Contact>) -> (Duration, usize) {
+    let start = Instant::now();
+    let mut count = 0;
+    for _ in 0..FRAMES {
+        count = find(bodies).len();
+    }
+    (start.elapsed() / FRAMES, count)
+}
+
+fn main() {
+    println!(
+        "{:>8} {:>14} {:>14} {:>10}",
+        "players", "brute force", "grid", "contacts"
+    );
+    for count in [4, 16, 64, 256, 512, 1024, 2048] {
+        let bodies = random_bodies(count);
+        let (brute_force, brute_force_count) = time(&bodies, brute_force_contacts);
+        let (grid, grid_count) = time(&bodies, |bodies| collision::find_contacts(bodies, STEP));
+        assert_eq!(
+            brute_force_count, grid_count,
+            "the grid should find the same contacts as checking every pair"
+        );
+        println!(
+            "{:>8} {:>14?} {:>14?} {:>10}",
+            count, brute_force, grid, grid_count
+        );
+    }
+}
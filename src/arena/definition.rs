@@ -0,0 +1,239 @@
+use std::{fs, path::Path};
+
+use bevy::{asset::FileAssetIo, prelude::*};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use super::{ArenaMotion, ArenaShape, HazardDefinition, ObstacleDefinition};
+use crate::prelude::*;
+
+/// Everything needed to build an arena, loaded from a `.ron` file in `assets/arenas`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ArenaDefinition {
+    /// Name of the file the arena was loaded from, without the extension
+    #[serde(skip)]
+    pub id: String,
+    /// Name to show players
+    pub name: String,
+    pub shape: ArenaShape,
+    /// Sprite to draw for the arena, relative to `assets`. If there isn't one, a sprite is
+    /// generated from the shape.
+    #[serde(default)]
+    pub texture: Option<String>,
+    /// Where players 0, 1, 2, and 3 spawn, relative to the center of the arena. Any further
+    /// players wrap around to the beginning of the list.
+    pub spawn_points: Vec<Vec2>,
+    /// Pillars, walls, and bumpers on the arena floor
+    #[serde(default)]
+    pub obstacles: Vec<ObstacleDefinition>,
+    /// Holes, conveyors, and ice on the arena floor
+    #[serde(default)]
+    pub hazards: Vec<HazardDefinition>,
+    /// How the arena moves, turns, and pulses during a round
+    #[serde(default)]
+    pub motion: ArenaMotion,
+}
+
+impl Default for ArenaDefinition {
+    fn default() -> Self {
+        Self {
+            id: "classic".to_string(),
+            name: "Classic".to_string(),
+            shape: ArenaShape::default(),
+            texture: Some("arena.png".to_string()),
+            spawn_points: DEFAULT_SPAWN_POINTS
+                .iter()
+                .map(|&point| Vec2::from(point))
+                .collect(),
+            obstacles: Vec::new(),
+            hazards: Vec::new(),
+            motion: ArenaMotion::default(),
+        }
+    }
+}
+
+impl ArenaDefinition {
+    /// Load a single arena definition
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        let mut definition: Self = ron::from_str(&contents).map_err(|error| error.to_string())?;
+        definition.id = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if definition.spawn_points.is_empty() {
+            return Err("an arena needs at least one spawn point".to_string());
+        }
+        definition.shape.validate()?;
+        for obstacle in definition.obstacles.iter() {
+            obstacle
+                .shape
+                .validate()
+                .map_err(|error| format!("obstacle: {}", error))?;
+        }
+        for hazard in definition.hazards.iter() {
+            hazard
+                .shape
+                .validate()
+                .map_err(|error| format!("hazard: {}", error))?;
+        }
+        definition.motion.validate()?;
+        Ok(definition)
+    }
+
+    /// Load every arena definition in a directory, sorted by file name
+    pub fn load_all(dir: impl AsRef<Path>) -> Vec<Self> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().map_or(false, |ext| ext == "ron"))
+            .collect();
+        paths.sort();
+        paths
+            .iter()
+            .filter_map(|path| match Self::load(path) {
+                Ok(definition) => Some(definition),
+                Err(error) => {
+                    println!("Skipping arena {}: {}", path.display(), error);
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// All of the arenas that can be played on
+#[derive(Resource, Clone, Debug)]
+pub struct ArenaLibrary {
+    pub arenas: Vec<ArenaDefinition>,
+}
+
+impl Default for ArenaLibrary {
+    fn default() -> Self {
+        // Look where the asset server does, so arenas are found no matter where the game is run
+        // from
+        let directory = FileAssetIo::get_base_path().join(ARENA_DIRECTORY);
+        let mut arenas = ArenaDefinition::load_all(&directory);
+        if arenas.is_empty() {
+            println!(
+                "No arenas found in {}, using the classic arena",
+                directory.display()
+            );
+            arenas.push(ArenaDefinition::default());
+        }
+        Self { arenas }
+    }
+}
+
+impl ArenaLibrary {
+    /// Look up an arena by file name (without the extension)
+    pub fn get(&self, id: &str) -> Option<&ArenaDefinition> {
+        self.arenas.iter().find(|arena| arena.id == id)
+    }
+
+    /// Where an arena is in the library, or the start of the library if it isn't there
+    fn index_of(&self, id: &str) -> usize {
+        self.arenas
+            .iter()
+            .position(|arena| arena.id == id)
+            .unwrap_or(0)
+    }
+
+    /// The arena after the given one, wrapping around to the start
+    pub fn next_after(&self, id: &str) -> &ArenaDefinition {
+        &self.arenas[(self.index_of(id) + 1) % self.arenas.len()]
+    }
+
+    /// The arena before the given one, wrapping around to the end
+    pub fn previous_before(&self, id: &str) -> &ArenaDefinition {
+        &self.arenas[(self.index_of(id) + self.arenas.len() - 1) % self.arenas.len()]
+    }
+
+    /// A random arena that isn't the given one (unless it's the only one there is)
+    pub fn random_other_than(&self, id: &str) -> &ArenaDefinition {
+        let others: Vec<&ArenaDefinition> =
+            self.arenas.iter().filter(|arena| arena.id != id).collect();
+        others
+            .choose(&mut rand::thread_rng())
+            .copied()
+            .unwrap_or(&self.arenas[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_bundled_arena_loads() {
+        let directory = FileAssetIo::get_base_path().join(ARENA_DIRECTORY);
+        let files = fs::read_dir(&directory).unwrap().count();
+        assert_eq!(ArenaDefinition::load_all(&directory).len(), files);
+    }
+
+    #[test]
+    fn bad_shapes_are_rejected() {
+        let bad_shapes = [
+            ArenaShape::Circle { radius: 0.0 },
+            ArenaShape::Rectangle {
+                half_size: Vec2::new(100.0, -1.0),
+            },
+            ArenaShape::RoundedRectangle {
+                half_size: Vec2::splat(100.0),
+                corner_radius: -5.0,
+            },
+            ArenaShape::Ring {
+                outer_radius: 100.0,
+                inner_radius: 200.0,
+            },
+            ArenaShape::Polygon {
+                points: vec![Vec2::ZERO, Vec2::X],
+            },
+        ];
+        for shape in bad_shapes {
+            assert!(shape.validate().is_err(), "{:?} should be rejected", shape);
+        }
+        assert!(ArenaShape::default().validate().is_ok());
+    }
+
+    #[test]
+    fn bad_motions_are_rejected() {
+        let bad_motions = [
+            ArenaMotion {
+                pulse: 1.0,
+                ..default()
+            },
+            ArenaMotion {
+                pulse: -0.1,
+                ..default()
+            },
+            ArenaMotion {
+                drift_period: -1.0,
+                ..default()
+            },
+            ArenaMotion {
+                pulse_period: -1.0,
+                ..default()
+            },
+            ArenaMotion {
+                friction: 1.5,
+                ..default()
+            },
+            ArenaMotion {
+                friction: -0.5,
+                ..default()
+            },
+        ];
+        for motion in bad_motions {
+            assert!(
+                motion.validate().is_err(),
+                "{:?} should be rejected",
+                motion
+            );
+        }
+        assert!(ArenaMotion::default().validate().is_ok());
+    }
+}
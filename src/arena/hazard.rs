@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::ArenaShape;
+use crate::prelude::*;
+
+/// How wide each stripe is on a conveyor sprite
+const CONVEYOR_STRIPE_WIDTH: f32 = 16.0;
+
+/// What a patch of floor does to players standing on it
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum HazardKind {
+    /// Falling in knocks you out, just like leaving the arena
+    Hole,
+    /// Carries players along at `velocity` (in arena units per second)
+    Conveyor { velocity: Vec2 },
+    /// Slippery floor: drag is multiplied by `drag` (so less than 1.0 is slipperier)
+    Ice { drag: f32 },
+}
+
+/// A hazard as described in an arena definition file
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HazardDefinition {
+    pub kind: HazardKind,
+    /// Center of the hazard, relative to the center of the arena
+    pub position: Vec2,
+    pub shape: ArenaShape,
+}
+
+/// A patch of the arena floor that changes the physics for players on it. Hazards are children of
+/// the `Arena` entity, and their position and shape are in the arena's local space.
+#[derive(Component, Clone, Debug)]
+pub struct Hazard {
+    pub kind: HazardKind,
+    pub position: Vec2,
+    pub shape: ArenaShape,
+}
+
+impl Hazard {
+    pub fn new(definition: &HazardDefinition) -> Self {
+        Self {
+            kind: definition.kind,
+            position: definition.position,
+            shape: definition.shape.clone(),
+        }
+    }
+
+    /// Whether a point in arena local space is on the hazard
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.shape.contains(point - self.position)
+    }
+
+    /// Sprite texture for the hazard
+    pub fn image(&self) -> Image {
+        match self.kind {
+            HazardKind::Hole => self.shape.image_with_colors([0, 0, 0], [20, 20, 20]),
+            HazardKind::Ice { .. } => self
+                .shape
+                .image_with_colors([170, 220, 240], [220, 245, 255]),
+            HazardKind::Conveyor { velocity } => self.conveyor_image(velocity),
+        }
+    }
+
+    /// Conveyors get stripes across the direction they move in
+    fn conveyor_image(&self, velocity: Vec2) -> Image {
+        let direction = velocity.normalize_or_zero();
+        let mut image = self.shape.image_with_colors([70, 70, 90], [40, 40, 50]);
+        let size = image.size();
+        let (width, height) = (size.x as usize, size.y as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let point = Vec2::new(
+                    x as f32 + 0.5 - width as f32 / 2.0,
+                    height as f32 / 2.0 - y as f32 - 0.5,
+                );
+                let stripe = (point.dot(direction) / CONVEYOR_STRIPE_WIDTH).floor() as i32;
+                if stripe % 2 == 0 {
+                    let pixel = &mut image.data[(y * width + x) * 4..(y * width + x) * 4 + 3];
+                    pixel.copy_from_slice(&[100, 100, 125]);
+                }
+            }
+        }
+        image
+    }
+
+    /// Local transform of the hazard sprite, relative to the arena
+    pub fn transform(&self) -> Transform {
+        Transform::from_translation(self.position.extend(LAYER_HAZARD - LAYER_ARENA))
+    }
+}
@@ -0,0 +1,445 @@
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+use crate::{
+    event::RoundResetEvent,
+    player::{Dead, Player},
+    prelude::*,
+    state::GameState,
+};
+
+mod definition;
+mod hazard;
+mod motion;
+mod obstacle;
+mod picker;
+mod shape;
+pub use definition::{ArenaDefinition, ArenaLibrary};
+pub use hazard::{Hazard, HazardDefinition, HazardKind};
+pub use motion::ArenaMotion;
+pub use obstacle::{Obstacle, ObstacleDefinition, ObstacleKind};
+pub use shape::ArenaShape;
+
+#[derive(Default)]
+pub struct ArenaPlugin;
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ArenaLibrary>()
+            .init_resource::<ArenaSettings>()
+            .init_resource::<ArenaShrink>()
+            .add_startup_system(spawn_arena_system)
+            .add_system(arena_shrink_system.in_set(OnUpdate(GameState::Playing)))
+            .add_system(
+                arena_motion_system
+                    .in_set(OnUpdate(GameState::Playing))
+                    .after(arena_shrink_system),
+            )
+            .add_system(arena_reset_system)
+            .add_startup_system(picker::start_picker_system)
+            .add_system(picker::spawn_picker_system.in_schedule(OnEnter(GameState::ArenaSelect)))
+            .add_system(picker::picker_system.in_set(OnUpdate(GameState::ArenaSelect)))
+            .add_system(picker::despawn_picker_system.in_schedule(OnExit(GameState::ArenaSelect)));
+    }
+}
+
+/// How the arena changes between rounds
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArenaRotation {
+    /// Keep playing on the same arena
+    #[default]
+    Fixed,
+    /// Go through the arenas in the `ArenaLibrary` in order
+    Cycle,
+    /// Pick a different arena at random
+    Random,
+}
+
+impl ArenaRotation {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "fixed" => Some(Self::Fixed),
+            "cycle" => Some(Self::Cycle),
+            "random" => Some(Self::Random),
+            _ => None,
+        }
+    }
+}
+
+/// Settings for the arena to spawn
+#[derive(Resource, Clone, Debug)]
+pub struct ArenaSettings {
+    /// Which arena from the `ArenaLibrary` to play on
+    pub arena: String,
+    /// Whether to let players pick the arena before the match starts
+    pub pick: bool,
+    pub rotation: ArenaRotation,
+}
+
+impl Default for ArenaSettings {
+    fn default() -> Self {
+        Self {
+            arena: DEFAULT_ARENA.to_string(),
+            pick: false,
+            rotation: ArenaRotation::Fixed,
+        }
+    }
+}
+
+/// The arena that players fight on. The shape and spawn points are in the arena's local space, so
+/// they move, turn, and scale along with the arena's transform.
+#[derive(Component, Clone, Debug)]
+pub struct Arena {
+    pub name: String,
+    pub shape: ArenaShape,
+    pub spawn_points: Vec<Vec2>,
+    pub motion: ArenaMotion,
+    /// How long the arena has been moving this round
+    pub elapsed: f32,
+    /// How much the arena has shrunk, in shrinking arena mode
+    pub shrink: f32,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self::new(&ArenaDefinition::default())
+    }
+}
+
+impl Arena {
+    pub fn new(definition: &ArenaDefinition) -> Self {
+        Self {
+            name: definition.name.clone(),
+            shape: definition.shape.clone(),
+            spawn_points: definition.spawn_points.clone(),
+            motion: definition.motion,
+            elapsed: 0.0,
+            shrink: 1.0,
+        }
+    }
+
+    /// Where the arena is at the start of a round
+    pub fn home_transform(&self) -> Transform {
+        self.motion.transform_at(0.0, 1.0)
+    }
+
+    /// Where a player should spawn, in world space. Falls back to the default spawn points if
+    /// there is no arena.
+    pub fn spawn_location(arena: Option<(&Arena, &Transform)>, player_id: PlayerID) -> Vec3 {
+        let point = match arena {
+            Some((arena, transform)) if !arena.spawn_points.is_empty() => Arena::to_world(
+                transform,
+                arena.spawn_points[player_id % arena.spawn_points.len()],
+            ),
+            _ => Vec2::from(DEFAULT_SPAWN_POINTS[player_id % DEFAULT_SPAWN_POINTS.len()]),
+        };
+        point.extend(LAYER_PLAYER)
+    }
+
+    /// Convert a point in world space into the arena's local space
+    pub fn to_local(transform: &Transform, point: Vec2) -> Vec2 {
+        transform
+            .compute_affine()
+            .inverse()
+            .transform_point3(point.extend(0.0))
+            .xy()
+    }
+
+    /// Convert a point in the arena's local space into world space
+    pub fn to_world(transform: &Transform, point: Vec2) -> Vec2 {
+        transform.transform_point(point.extend(0.0)).xy()
+    }
+
+    /// Whether a point in world space is on the arena floor
+    pub fn contains(&self, transform: &Transform, point: Vec2) -> bool {
+        self.shape.contains(Arena::to_local(transform, point))
+    }
+
+    /// A random point in world space that is at least `margin` (in local units) inside the edge
+    pub fn random_point(&self, transform: &Transform, margin: f32) -> Vec2 {
+        Arena::to_world(transform, self.shape.random_point(margin))
+    }
+
+    /// A random point in world space that is at least `margin` (in local units) inside the edge
+    /// and away from every obstacle and hazard, or `None` if no such spot turns up
+    pub fn random_clear_point(
+        &self,
+        transform: &Transform,
+        margin: f32,
+        obstacles: &[&Obstacle],
+        hazards: &[&Hazard],
+    ) -> Option<Vec2> {
+        (0..RANDOM_POINT_ATTEMPTS)
+            .map(|_| self.shape.random_point(margin))
+            .find(|&point| {
+                // The shape settles for any point if nothing fits, so check it's on the floor
+                self.shape.contains(point)
+                    && obstacles
+                        .iter()
+                        .all(|obstacle| obstacle.penetration(point, margin).is_none())
+                    && hazards
+                        .iter()
+                        .all(|hazard| hazard.shape.distance(point - hazard.position) >= margin)
+            })
+            .map(|point| Arena::to_world(transform, point))
+    }
+
+    /// Whether a point in world space is somewhere a player could stand: on the floor, outside
+    /// every obstacle, and not over a hole
+    pub fn can_stand_at(
+        &self,
+        transform: &Transform,
+        point: Vec2,
+        obstacles: &[&Obstacle],
+        hazards: &[&Hazard],
+    ) -> bool {
+        let point = Arena::to_local(transform, point);
+        self.shape.contains(point)
+            && obstacles
+                .iter()
+                .all(|obstacle| obstacle.penetration(point, 0.0).is_none())
+            && !hazards
+                .iter()
+                .any(|hazard| hazard.kind == HazardKind::Hole && hazard.contains(point))
+    }
+}
+
+/// One point on the shrinking arena schedule: `scale` is how big the arena should be (as a fraction
+/// of its full size) once `seconds` have passed since the start of the round.
+#[derive(Clone, Copy, Debug)]
+pub struct ShrinkStep {
+    pub seconds: f32,
+    pub scale: f32,
+}
+
+impl ShrinkStep {
+    pub fn new(seconds: f32, scale: f32) -> Self {
+        Self { seconds, scale }
+    }
+}
+
+/// Settings for the shrinking arena mode. The arena scale is linearly interpolated between the
+/// steps of the schedule, and holds at the last step until the round is over.
+#[derive(Resource, Clone, Debug)]
+pub struct ArenaShrink {
+    pub enabled: bool,
+    /// Steps of the schedule, sorted by `seconds`
+    pub schedule: Vec<ShrinkStep>,
+    /// How long the current round has been going
+    elapsed: f32,
+}
+
+impl Default for ArenaShrink {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            schedule: vec![
+                ShrinkStep::new(0.0, 1.0),
+                ShrinkStep::new(SHRINK_START, 1.0),
+                ShrinkStep::new(SHRINK_END, SHRINK_MIN_SCALE),
+            ],
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl ArenaShrink {
+    /// The default schedule, turned on
+    pub fn enabled() -> Self {
+        Self {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    /// What the arena scale should be at the given number of seconds into the round
+    pub fn scale_at(&self, seconds: f32) -> f32 {
+        let mut previous: Option<&ShrinkStep> = None;
+        for step in self.schedule.iter() {
+            if seconds < step.seconds {
+                return match previous {
+                    Some(prev) => {
+                        let t = (seconds - prev.seconds) / (step.seconds - prev.seconds);
+                        prev.scale + (step.scale - prev.scale) * t
+                    }
+                    None => step.scale,
+                };
+            }
+            previous = Some(step);
+        }
+        previous.map_or(1.0, |step| step.scale)
+    }
+}
+
+/// Spawn an arena entity from its definition
+fn spawn_arena(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    images: &mut Assets<Image>,
+    definition: &ArenaDefinition,
+) {
+    println!("Arena: {}", definition.name);
+    let texture = match &definition.texture {
+        Some(path) => asset_server.load(path.as_str()),
+        None => images.add(definition.shape.image()),
+    };
+    let arena = Arena::new(definition);
+    commands
+        .spawn(SpriteBundle {
+            texture,
+            transform: arena.home_transform(),
+            ..Default::default()
+        })
+        .insert(arena)
+        .with_children(|parent| {
+            for hazard in definition.hazards.iter().map(Hazard::new) {
+                parent
+                    .spawn(SpriteBundle {
+                        texture: images.add(hazard.image()),
+                        transform: hazard.transform(),
+                        ..Default::default()
+                    })
+                    .insert(hazard);
+            }
+            for obstacle in definition.obstacles.iter().map(Obstacle::new) {
+                parent
+                    .spawn(SpriteBundle {
+                        texture: images.add(obstacle.image()),
+                        transform: obstacle.transform(),
+                        ..Default::default()
+                    })
+                    .insert(obstacle);
+            }
+        });
+}
+
+/// Despawn the current arena and spawn a different one in its place, moving players to the new
+/// arena's spawn points
+fn replace_arena(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    images: &mut Assets<Image>,
+    definition: &ArenaDefinition,
+    arena_query: &Query<Entity, With<Arena>>,
+    player_query: &mut Query<(&mut Player, &mut Transform), Without<Arena>>,
+) {
+    for entity in arena_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_arena(commands, asset_server, images, definition);
+    let arena = Arena::new(definition);
+    reset_players(&arena, &arena.home_transform(), player_query);
+}
+
+/// Put every player back on their spawn point, standing still
+fn reset_players(
+    arena: &Arena,
+    arena_transform: &Transform,
+    player_query: &mut Query<(&mut Player, &mut Transform), Without<Arena>>,
+) {
+    for (mut player, mut transform) in player_query.iter_mut() {
+        transform.translation = Arena::spawn_location(Some((arena, arena_transform)), player.id);
+        player.vel = Vec2::ZERO;
+        player.damage = 0.0;
+    }
+}
+
+fn spawn_arena_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    arena_library: Res<ArenaLibrary>,
+    mut arena_settings: ResMut<ArenaSettings>,
+) {
+    let definition = arena_library.get(&arena_settings.arena).unwrap_or_else(|| {
+        println!(
+            "Unknown arena {}, using {}",
+            arena_settings.arena, arena_library.arenas[0].id
+        );
+        &arena_library.arenas[0]
+    });
+    arena_settings.arena = definition.id.clone();
+    spawn_arena(&mut commands, &asset_server, &mut images, definition);
+}
+
+/// Shrink the arena over the course of a round, if the shrinking arena mode is turned on
+fn arena_shrink_system(
+    time: Res<Time>,
+    mut arena_shrink: ResMut<ArenaShrink>,
+    mut arena_query: Query<&mut Arena>,
+) {
+    if !arena_shrink.enabled {
+        return;
+    }
+    arena_shrink.elapsed += time.delta_seconds();
+    let scale = arena_shrink.scale_at(arena_shrink.elapsed);
+    for mut arena in arena_query.iter_mut() {
+        arena.shrink = scale;
+    }
+}
+
+/// Carry players along with the floor as the arena moves
+fn arena_motion_system(
+    time: Res<Time>,
+    mut arena_query: Query<(&mut Arena, &mut Transform), Without<Player>>,
+    mut player_query: Query<&mut Transform, (With<Player>, Without<Dead>)>,
+) {
+    for (mut arena, mut transform) in arena_query.iter_mut() {
+        let previous = arena.motion.transform_at(arena.elapsed, arena.shrink);
+        arena.elapsed += time.delta_seconds();
+        let next = arena.motion.transform_at(arena.elapsed, arena.shrink);
+        // Only the arena's own motion carries players -- if shrinking carried them too, nobody
+        // would ever get squeezed off the edge
+        if arena.motion.friction > 0.0 && next != previous {
+            for mut player_transform in player_query.iter_mut() {
+                let position = player_transform.translation.xy();
+                let local_position = Arena::to_local(&previous, position);
+                if !arena.shape.contains(local_position) {
+                    continue;
+                }
+                let moved = Arena::to_world(&next, local_position) - position;
+                player_transform.translation += (moved * arena.motion.friction).extend(0.0);
+            }
+        }
+        *transform = next;
+    }
+}
+
+/// When a new round starts, switch to the next arena if arena rotation is turned on, or put the
+/// current arena back the way it started. Either way, players go back to their spawn points.
+#[allow(clippy::too_many_arguments)]
+fn arena_reset_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    arena_library: Res<ArenaLibrary>,
+    mut arena_settings: ResMut<ArenaSettings>,
+    mut arena_shrink: ResMut<ArenaShrink>,
+    mut round_reset_events: EventReader<RoundResetEvent>,
+    mut arena_query: Query<(Entity, &mut Arena, &mut Transform)>,
+    mut player_query: Query<(&mut Player, &mut Transform), Without<Arena>>,
+) {
+    if round_reset_events.iter().next().is_none() {
+        return;
+    }
+    arena_shrink.elapsed = 0.0;
+    let next = match arena_settings.rotation {
+        ArenaRotation::Fixed => None,
+        ArenaRotation::Cycle => Some(arena_library.next_after(&arena_settings.arena)),
+        ArenaRotation::Random => Some(arena_library.random_other_than(&arena_settings.arena)),
+    };
+    if let Some(definition) = next {
+        arena_settings.arena = definition.id.clone();
+        for (entity, _, _) in arena_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        spawn_arena(&mut commands, &asset_server, &mut images, definition);
+        let arena = Arena::new(definition);
+        reset_players(&arena, &arena.home_transform(), &mut player_query);
+        return;
+    }
+    for (_, mut arena, mut transform) in arena_query.iter_mut() {
+        arena.elapsed = 0.0;
+        arena.shrink = 1.0;
+        *transform = arena.home_transform();
+        reset_players(&arena, &transform, &mut player_query);
+    }
+}
@@ -0,0 +1,82 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+/// How an arena moves over the course of a round. All of the motions are optional, and combine
+/// with each other (and with the shrinking arena mode).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct ArenaMotion {
+    /// How fast the arena turns, in radians per second (positive is counter-clockwise)
+    pub rotation_speed: f32,
+    /// How far the arena drifts away from the center of the screen, back and forth
+    pub drift: Vec2,
+    /// How many seconds it takes to drift there and back again
+    pub drift_period: f32,
+    /// How much the arena grows and shrinks, as a fraction of its size
+    pub pulse: f32,
+    /// How many seconds it takes to grow and shrink again
+    pub pulse_period: f32,
+    /// How much players get carried along with the floor as it moves, from 0.0 (the floor slides
+    /// out from under them) to 1.0 (they move right along with it)
+    pub friction: f32,
+}
+
+impl Default for ArenaMotion {
+    fn default() -> Self {
+        Self {
+            rotation_speed: 0.0,
+            drift: Vec2::ZERO,
+            drift_period: 10.0,
+            pulse: 0.0,
+            pulse_period: 10.0,
+            friction: 1.0,
+        }
+    }
+}
+
+impl ArenaMotion {
+    /// Make sure the motion makes sense: the arena can't pulse down to nothing (or inside out),
+    /// periods can't be negative, and friction has to be between 0.0 and 1.0
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..1.0).contains(&self.pulse) {
+            return Err(format!(
+                "pulse has to be at least 0.0 and less than 1.0, not {}",
+                self.pulse
+            ));
+        }
+        if self.drift_period < 0.0 || self.pulse_period < 0.0 {
+            return Err("motion periods can't be negative".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.friction) {
+            return Err(format!(
+                "friction has to be between 0.0 and 1.0, not {}",
+                self.friction
+            ));
+        }
+        Ok(())
+    }
+
+    /// Where the arena should be `seconds` into the round, given how much it has shrunk
+    pub fn transform_at(&self, seconds: f32, shrink: f32) -> Transform {
+        let drift = if self.drift_period > 0.0 {
+            self.drift * (seconds * TAU / self.drift_period).sin()
+        } else {
+            Vec2::ZERO
+        };
+        let pulse = if self.pulse_period > 0.0 {
+            1.0 + self.pulse * (seconds * TAU / self.pulse_period).sin()
+        } else {
+            1.0
+        };
+        let scale = shrink * pulse;
+        Transform {
+            translation: drift.extend(LAYER_ARENA),
+            rotation: Quat::from_rotation_z(self.rotation_speed * seconds),
+            scale: Vec3::new(scale, scale, 1.0),
+        }
+    }
+}
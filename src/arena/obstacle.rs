@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::ArenaShape;
+use crate::prelude::*;
+
+/// What an obstacle does when a player runs into it
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ObstacleKind {
+    /// A solid post that players bounce off of
+    Pillar,
+    /// A solid barrier that players bounce off of
+    Wall,
+    /// Like a pinball bumper: kicks players away with extra velocity on contact
+    Bumper { boost: f32 },
+}
+
+impl ObstacleKind {
+    /// Fill and edge colors for the generated sprite
+    fn colors(self) -> ([u8; 3], [u8; 3]) {
+        match self {
+            ObstacleKind::Pillar => ([110, 110, 110], [40, 40, 40]),
+            ObstacleKind::Wall => ([151, 86, 40], [90, 50, 20]),
+            ObstacleKind::Bumper { .. } => ([200, 40, 60], [250, 220, 90]),
+        }
+    }
+}
+
+/// An obstacle as described in an arena definition file
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ObstacleDefinition {
+    pub kind: ObstacleKind,
+    /// Center of the obstacle, relative to the center of the arena
+    pub position: Vec2,
+    pub shape: ArenaShape,
+}
+
+/// An obstacle on the arena floor. Obstacles are children of the `Arena` entity, and their
+/// position and shape are in the arena's local space.
+#[derive(Component, Clone, Debug)]
+pub struct Obstacle {
+    pub kind: ObstacleKind,
+    pub position: Vec2,
+    pub shape: ArenaShape,
+}
+
+impl Obstacle {
+    pub fn new(definition: &ObstacleDefinition) -> Self {
+        Self {
+            kind: definition.kind,
+            position: definition.position,
+            shape: definition.shape.clone(),
+        }
+    }
+
+    /// If a circle (in arena local space) overlaps the obstacle, return the direction to push it
+    /// out and how far it needs to go
+    pub fn penetration(&self, center: Vec2, radius: f32) -> Option<(Vec2, f32)> {
+        let point = center - self.position;
+        let distance = self.shape.distance(point);
+        if distance >= radius {
+            return None;
+        }
+        Some((self.shape.normal(point), radius - distance))
+    }
+
+    /// Sprite texture for the obstacle
+    pub fn image(&self) -> Image {
+        let (fill, edge) = self.kind.colors();
+        self.shape.image_with_colors(fill, edge)
+    }
+
+    /// Local transform of the obstacle sprite, relative to the arena
+    pub fn transform(&self) -> Transform {
+        Transform::from_translation(self.position.extend(LAYER_OBSTACLE - LAYER_ARENA))
+    }
+}
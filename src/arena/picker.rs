@@ -0,0 +1,111 @@
+use bevy::prelude::*;
+
+use super::{replace_arena, Arena, ArenaLibrary, ArenaSettings};
+use crate::{player::Player, prelude::*, state::GameState};
+
+/// Marker for the arena picker text
+#[derive(Component, Copy, Clone, Default)]
+pub struct ArenaPicker;
+
+/// Start out picking an arena, if the settings ask for it
+pub(super) fn start_picker_system(
+    arena_library: Res<ArenaLibrary>,
+    arena_settings: Res<ArenaSettings>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if arena_settings.pick && arena_library.arenas.len() > 1 {
+        next_state.set(GameState::ArenaSelect);
+    }
+}
+
+/// The text telling players which arena is selected and how to pick
+fn picker_text(arena_library: &ArenaLibrary, arena_settings: &ArenaSettings) -> String {
+    let name = arena_library
+        .get(&arena_settings.arena)
+        .map_or("?", |definition| definition.name.as_str());
+    format!("< {} >\nD-pad to choose, A to fight", name)
+}
+
+pub(super) fn spawn_picker_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    arena_library: Res<ArenaLibrary>,
+    arena_settings: Res<ArenaSettings>,
+) {
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                picker_text(&arena_library, &arena_settings),
+                TextStyle {
+                    font: asset_server.load("FiraMono-Medium.ttf"),
+                    font_size: 36.0,
+                    color: Color::WHITE,
+                },
+            )
+            .with_alignment(TextAlignment::Center),
+            transform: Transform::from_translation(Vec3::new(0.0, 440.0, LAYER_BRACKET)),
+            ..default()
+        })
+        .insert(ArenaPicker);
+}
+
+/// Flip through the arenas (showing each one as it's selected) until someone picks one
+#[allow(clippy::too_many_arguments)]
+pub(super) fn picker_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    arena_library: Res<ArenaLibrary>,
+    mut arena_settings: ResMut<ArenaSettings>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    arena_query: Query<Entity, With<Arena>>,
+    mut player_query: Query<(&mut Player, &mut Transform), Without<Arena>>,
+    mut picker_query: Query<&mut Text, With<ArenaPicker>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let pressed = |button_type: GamepadButtonType, key_code: KeyCode| {
+        keyboard_input.just_pressed(key_code)
+            || gamepads
+                .iter()
+                .any(|gamepad| buttons.just_pressed(GamepadButton::new(gamepad, button_type)))
+    };
+    if pressed(GamepadButtonType::South, KeyCode::Return) {
+        println!("Fighting on {}", arena_settings.arena);
+        next_state.set(GameState::Playing);
+        return;
+    }
+    let definition = if pressed(GamepadButtonType::DPadRight, KeyCode::Right)
+        || pressed(GamepadButtonType::DPadDown, KeyCode::Down)
+    {
+        arena_library.next_after(&arena_settings.arena)
+    } else if pressed(GamepadButtonType::DPadLeft, KeyCode::Left)
+        || pressed(GamepadButtonType::DPadUp, KeyCode::Up)
+    {
+        arena_library.previous_before(&arena_settings.arena)
+    } else {
+        return;
+    };
+    arena_settings.arena = definition.id.clone();
+    replace_arena(
+        &mut commands,
+        &asset_server,
+        &mut images,
+        definition,
+        &arena_query,
+        &mut player_query,
+    );
+    for mut text in picker_query.iter_mut() {
+        text.sections[0].value = picker_text(&arena_library, &arena_settings);
+    }
+}
+
+pub(super) fn despawn_picker_system(
+    mut commands: Commands,
+    picker_query: Query<Entity, With<ArenaPicker>>,
+) {
+    for entity in picker_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,368 @@
+use bevy::{
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+/// Color of the arena floor in generated arena sprites -- matches arena.png
+const FLOOR_COLOR: [u8; 3] = [52, 52, 52];
+/// Color of the arena edge in generated arena sprites -- matches arena.png
+const EDGE_COLOR: [u8; 3] = [151, 86, 40];
+/// How thick the edge is in generated arena sprites
+const EDGE_WIDTH: f32 = 6.0;
+
+/// The geometry of an arena floor or obstacle, centered on its own origin.
+/// Everything is based on a signed distance function, which is negative on the floor and positive
+/// out of bounds, so out-of-bounds tests and generated sprites always agree.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ArenaShape {
+    Circle {
+        radius: f32,
+    },
+    Rectangle {
+        half_size: Vec2,
+    },
+    RoundedRectangle {
+        half_size: Vec2,
+        corner_radius: f32,
+    },
+    /// A circle with a circular hole in the middle
+    Ring {
+        outer_radius: f32,
+        inner_radius: f32,
+    },
+    /// Any simple polygon (convex or not), with points in order around the edge
+    Polygon {
+        points: Vec<Vec2>,
+    },
+}
+
+impl Default for ArenaShape {
+    fn default() -> Self {
+        Self::Circle {
+            radius: ARENA_RADIUS,
+        }
+    }
+}
+
+impl ArenaShape {
+    /// Signed distance from a point in local space to the edge of the floor: negative on the
+    /// floor, positive out of bounds
+    pub fn distance(&self, point: Vec2) -> f32 {
+        match self {
+            Self::Circle { radius } => point.length() - radius,
+            Self::Rectangle { half_size } => box_distance(point, *half_size),
+            Self::RoundedRectangle {
+                half_size,
+                corner_radius,
+            } => {
+                let corner_radius = corner_radius.min(half_size.min_element());
+                box_distance(point, *half_size - Vec2::splat(corner_radius)) - corner_radius
+            }
+            Self::Ring {
+                outer_radius,
+                inner_radius,
+            } => {
+                let length = point.length();
+                (length - outer_radius).max(inner_radius - length)
+            }
+            Self::Polygon { points } => polygon_distance(point, points),
+        }
+    }
+
+    /// Whether a point in local space is on the arena floor
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.distance(point) <= 0.0
+    }
+
+    /// Make sure the shape makes sense: sizes have to be positive, and a polygon needs at least
+    /// three points
+    pub fn validate(&self) -> Result<(), String> {
+        let valid = match self {
+            Self::Circle { radius } => *radius > 0.0,
+            Self::Rectangle { half_size } => half_size.cmpgt(Vec2::ZERO).all(),
+            Self::RoundedRectangle {
+                half_size,
+                corner_radius,
+            } => half_size.cmpgt(Vec2::ZERO).all() && *corner_radius >= 0.0,
+            Self::Ring {
+                outer_radius,
+                inner_radius,
+            } => *inner_radius >= 0.0 && outer_radius > inner_radius,
+            Self::Polygon { points } => {
+                if points.len() < 3 {
+                    return Err("a polygon needs at least three points".to_string());
+                }
+                true
+            }
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("{:?} needs a positive size", self))
+        }
+    }
+
+    /// Half the size of a box that the whole shape fits in
+    pub fn half_extents(&self) -> Vec2 {
+        match self {
+            Self::Circle { radius } => Vec2::splat(*radius),
+            Self::Rectangle { half_size } => *half_size,
+            Self::RoundedRectangle { half_size, .. } => *half_size,
+            Self::Ring { outer_radius, .. } => Vec2::splat(*outer_radius),
+            Self::Polygon { points } => points
+                .iter()
+                .fold(Vec2::ZERO, |extents, point| extents.max(point.abs())),
+        }
+    }
+
+    /// A random point in local space that is at least `margin` inside the edge of the floor
+    pub fn random_point(&self, margin: f32) -> Vec2 {
+        let mut rng = rand::thread_rng();
+        let extents = self.half_extents();
+        // If nothing fits with the margin, settle for anywhere on the floor
+        for margin in [margin, 0.0] {
+            for _ in 0..100 {
+                let point = Vec2::new(
+                    rng.gen_range(-extents.x..=extents.x),
+                    rng.gen_range(-extents.y..=extents.y),
+                );
+                if self.distance(point) <= -margin {
+                    return point;
+                }
+            }
+        }
+        Vec2::ZERO
+    }
+
+    /// Direction pointing away from the inside of the shape, at a given point in local space
+    pub fn normal(&self, point: Vec2) -> Vec2 {
+        // The gradient of the signed distance function
+        let epsilon = 0.5;
+        Vec2::new(
+            self.distance(point + Vec2::X * epsilon) - self.distance(point - Vec2::X * epsilon),
+            self.distance(point + Vec2::Y * epsilon) - self.distance(point - Vec2::Y * epsilon),
+        )
+        .normalize_or_zero()
+    }
+
+    /// Draw the shape into a texture with the same colors as arena.png
+    pub fn image(&self) -> Image {
+        self.image_with_colors(FLOOR_COLOR, EDGE_COLOR)
+    }
+
+    /// Draw the shape into a texture: the inside and edge are opaque, and the outside is
+    /// transparent. One pixel is one unit of local space.
+    pub fn image_with_colors(&self, fill: [u8; 3], edge: [u8; 3]) -> Image {
+        let extents = self.half_extents() + Vec2::splat(2.0);
+        let width = (extents.x * 2.0).ceil() as u32;
+        let height = (extents.y * 2.0).ceil() as u32;
+        let mut data = Vec::with_capacity((width * height * 4) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                // Image rows go down, but local space y goes up
+                let point = Vec2::new(
+                    x as f32 + 0.5 - width as f32 / 2.0,
+                    height as f32 / 2.0 - y as f32 - 0.5,
+                );
+                let distance = self.distance(point);
+                let color = if distance > -EDGE_WIDTH { edge } else { fill };
+                // Antialias the outside edge
+                let alpha = (0.5 - distance).clamp(0.0, 1.0);
+                data.extend_from_slice(&[color[0], color[1], color[2], (alpha * 255.0) as u8]);
+            }
+        }
+        Image::new(
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+        )
+    }
+}
+
+/// Signed distance to an axis-aligned box centered on the origin
+fn box_distance(point: Vec2, half_size: Vec2) -> f32 {
+    let q = point.abs() - half_size;
+    q.max(Vec2::ZERO).length() + q.max_element().min(0.0)
+}
+
+/// Signed distance to a simple polygon, from https://iquilezles.org/articles/distfunctions2d/
+fn polygon_distance(point: Vec2, points: &[Vec2]) -> f32 {
+    if points.len() < 3 {
+        return f32::INFINITY;
+    }
+    let mut distance_squared = (point - points[0]).length_squared();
+    let mut sign = 1.0;
+    let mut j = points.len() - 1;
+    for i in 0..points.len() {
+        let edge = points[j] - points[i];
+        let to_point = point - points[i];
+        let closest =
+            to_point - edge * (to_point.dot(edge) / edge.length_squared()).clamp(0.0, 1.0);
+        distance_squared = distance_squared.min(closest.length_squared());
+        // Count edge crossings to figure out whether we're inside
+        let conditions = [
+            point.y >= points[i].y,
+            point.y < points[j].y,
+            edge.x * to_point.y > edge.y * to_point.x,
+        ];
+        if conditions.iter().all(|&c| c) || conditions.iter().all(|&c| !c) {
+            sign = -sign;
+        }
+        j = i;
+    }
+    sign * distance_squared.sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check that every point in `inside` is on the floor and every point in `outside` isn't
+    fn check(shape: ArenaShape, inside: &[(f32, f32)], outside: &[(f32, f32)]) {
+        for &point in inside {
+            assert!(
+                shape.contains(Vec2::from(point)),
+                "{:?} should be inside {:?}",
+                point,
+                shape
+            );
+        }
+        for &point in outside {
+            assert!(
+                !shape.contains(Vec2::from(point)),
+                "{:?} should be outside {:?}",
+                point,
+                shape
+            );
+        }
+    }
+
+    #[test]
+    fn circle() {
+        check(
+            ArenaShape::Circle { radius: 100.0 },
+            &[(0.0, 0.0), (99.0, 0.0), (0.0, -99.0), (70.0, 70.0)],
+            &[(101.0, 0.0), (0.0, -101.0), (71.0, 71.0)],
+        );
+    }
+
+    #[test]
+    fn rectangle() {
+        check(
+            ArenaShape::Rectangle {
+                half_size: Vec2::new(200.0, 100.0),
+            },
+            &[(0.0, 0.0), (199.0, 99.0), (-199.0, -99.0), (150.0, 0.0)],
+            &[(0.0, 101.0), (150.0, 101.0), (201.0, 0.0), (-201.0, -101.0)],
+        );
+    }
+
+    #[test]
+    fn rounded_rectangle() {
+        check(
+            ArenaShape::RoundedRectangle {
+                half_size: Vec2::new(200.0, 100.0),
+                corner_radius: 50.0,
+            },
+            &[(0.0, 0.0), (199.0, 0.0), (0.0, 99.0), (180.0, 80.0)],
+            // The corners are cut off, so the box's corner points are out of bounds
+            &[(199.0, 99.0), (-199.0, -99.0), (201.0, 0.0), (0.0, -101.0)],
+        );
+    }
+
+    #[test]
+    fn ring() {
+        check(
+            ArenaShape::Ring {
+                outer_radius: 300.0,
+                inner_radius: 100.0,
+            },
+            &[(200.0, 0.0), (0.0, -101.0), (-299.0, 0.0), (150.0, 150.0)],
+            // Falling into the hole counts as out of bounds too
+            &[
+                (0.0, 0.0),
+                (99.0, 0.0),
+                (50.0, 50.0),
+                (301.0, 0.0),
+                (0.0, -301.0),
+            ],
+        );
+    }
+
+    #[test]
+    fn convex_polygon() {
+        check(
+            ArenaShape::Polygon {
+                points: vec![
+                    Vec2::new(0.0, 100.0),
+                    Vec2::new(-100.0, -100.0),
+                    Vec2::new(100.0, -100.0),
+                ],
+            },
+            &[(0.0, 0.0), (0.0, 99.0), (-99.0, -99.0), (50.0, -50.0)],
+            &[(0.0, 101.0), (-60.0, 50.0), (60.0, 50.0), (0.0, -101.0)],
+        );
+    }
+
+    #[test]
+    fn concave_polygon() {
+        // The same cross as the crossroads arena
+        let points = [
+            (150.0, 150.0),
+            (150.0, 384.0),
+            (-150.0, 384.0),
+            (-150.0, 150.0),
+            (-384.0, 150.0),
+            (-384.0, -150.0),
+            (-150.0, -150.0),
+            (-150.0, -384.0),
+            (150.0, -384.0),
+            (150.0, -150.0),
+            (384.0, -150.0),
+            (384.0, 150.0),
+        ];
+        check(
+            ArenaShape::Polygon {
+                points: points.iter().map(|&point| Vec2::from(point)).collect(),
+            },
+            &[
+                (0.0, 0.0),
+                (0.0, 380.0),
+                (380.0, 0.0),
+                (-380.0, 0.0),
+                (0.0, -380.0),
+                (149.0, 149.0),
+            ],
+            // The notches between the arms are out of bounds
+            &[
+                (200.0, 200.0),
+                (-200.0, 200.0),
+                (-200.0, -200.0),
+                (200.0, -200.0),
+                (151.0, 151.0),
+                (0.0, 390.0),
+                (390.0, 0.0),
+            ],
+        );
+    }
+
+    #[test]
+    fn ring_distance_is_signed() {
+        let shape = ArenaShape::Ring {
+            outer_radius: 300.0,
+            inner_radius: 100.0,
+        };
+        assert!((shape.distance(Vec2::new(200.0, 0.0)) + 100.0).abs() < 1e-3);
+        assert!((shape.distance(Vec2::new(50.0, 0.0)) - 50.0).abs() < 1e-3);
+        assert!((shape.distance(Vec2::new(400.0, 0.0)) - 100.0).abs() < 1e-3);
+    }
+}
@@ -1,10 +1,14 @@
 use bevy::prelude::*;
 
+use crate::prelude::*;
+
 #[derive(Default)]
 pub struct EventPlugin;
 impl Plugin for EventPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<PlayerSpawnEvent>();
+        app.add_event::<PlayerSpawnEvent>()
+            .add_event::<RoundResetEvent>()
+            .add_event::<MatchOverEvent>();
     }
 }
 
@@ -12,3 +16,14 @@ impl Plugin for EventPlugin {
 pub struct PlayerSpawnEvent {
     pub id: usize,
 }
+
+/// Sent when the game is reset after someone wins a round, so anything that changes over the course
+/// of a round can put itself back the way it started.
+#[derive(Default)]
+pub struct RoundResetEvent;
+
+/// Sent when someone has won enough rounds to win the match
+#[derive(Default)]
+pub struct MatchOverEvent {
+    pub winner: PlayerID,
+}
@@ -1,4 +1,4 @@
-use crate::event::PlayerSpawnEvent;
+use crate::{event::PlayerSpawnEvent, state::GameState};
 use bevy::prelude::*;
 use bevy::{
     app::AppExit,
@@ -12,14 +12,20 @@ impl Plugin for GamepadPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(GamepadManager::default())
             .insert_resource(GamepadInputs::default())
-            .add_system(axis_system)
-            .add_system(button_system)
+            .add_system(axis_system.in_set(GamepadInputSet))
+            .add_system(button_system.in_set(GamepadInputSet))
             .add_startup_system(connection_system)
             .add_system(connection_system)
-            .add_system(keyboard_quit_system);
+            .add_system(keyboard_quit_system)
+            .add_system(clear_inputs_system.in_schedule(OnEnter(GameState::Playing)));
     }
 }
 
+/// The systems that cache this frame's gamepad input. Anything that reads `GamepadInputs` should
+/// run after this, so button presses are seen on the frame they happen, and only once.
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GamepadInputSet;
+
 #[derive(Default, Resource)]
 pub struct GamepadInputs {
     pub inputs: HashMap<usize, GamepadInput>,
@@ -31,6 +37,30 @@ pub struct GamepadInput {
     pub left_stick: Vec2,
     pub right_stick: Vec2,
     pub right_trigger2: bool,
+    /// Whether the right trigger went down this frame
+    pub right_trigger2_pressed: bool,
+    /// Whether the right trigger came up this frame
+    pub right_trigger2_released: bool,
+    /// Whether the dash button (A) went down this frame
+    pub dash_pressed: bool,
+    /// Whether the block button (left trigger) is held down
+    pub block: bool,
+    /// Whether the grab button (right bumper) went down this frame
+    pub grab_pressed: bool,
+    /// Whether the throw button (X) went down this frame
+    pub throw_pressed: bool,
+    /// Whether the fire glove button (Y) went down this frame
+    pub fire_glove_pressed: bool,
+    /// Whether any of the face buttons went down this frame, for mashing out of a grab
+    pub mash: bool,
+}
+
+/// Forget any button presses cached while a menu was up, so the A press that leaves the arena
+/// picker or the bracket screen doesn't also make someone dash on the first frame of play
+fn clear_inputs_system(mut gamepad_inputs: ResMut<GamepadInputs>) {
+    for input in gamepad_inputs.inputs.values_mut() {
+        *input = GamepadInput::default();
+    }
 }
 
 #[derive(Default, Resource)]
@@ -106,10 +136,12 @@ pub fn button_system(
     for gamepad in manager.gamepad.iter() {
         let gamepad_input = gamepad_inputs.inputs.entry(gamepad.id).or_default();
         let mut right_trigger2 = false;
+        let mut block = false;
         for button_code in button_codes.iter() {
             if inputs.pressed(GamepadButton::new(*gamepad, *button_code)) {
                 match button_code {
                     GamepadButtonType::RightTrigger2 => right_trigger2 = true,
+                    GamepadButtonType::LeftTrigger2 => block = true,
                     GamepadButtonType::Start => {
                         println!("Thank you for playing!");
                         app_exit_events.send(AppExit);
@@ -124,6 +156,28 @@ pub fn button_system(
             // }
         }
         gamepad_input.right_trigger2 = right_trigger2;
+        gamepad_input.block = block;
+        let right_trigger2_button = GamepadButton::new(*gamepad, GamepadButtonType::RightTrigger2);
+        gamepad_input.right_trigger2_pressed = inputs.just_pressed(right_trigger2_button);
+        gamepad_input.right_trigger2_released = inputs.just_released(right_trigger2_button);
+        gamepad_input.dash_pressed =
+            inputs.just_pressed(GamepadButton::new(*gamepad, GamepadButtonType::South));
+        gamepad_input.grab_pressed = inputs.just_pressed(GamepadButton::new(
+            *gamepad,
+            GamepadButtonType::RightTrigger,
+        ));
+        gamepad_input.throw_pressed =
+            inputs.just_pressed(GamepadButton::new(*gamepad, GamepadButtonType::West));
+        gamepad_input.fire_glove_pressed =
+            inputs.just_pressed(GamepadButton::new(*gamepad, GamepadButtonType::North));
+        gamepad_input.mash = [
+            GamepadButtonType::South,
+            GamepadButtonType::East,
+            GamepadButtonType::North,
+            GamepadButtonType::West,
+        ]
+        .iter()
+        .any(|button_type| inputs.just_pressed(GamepadButton::new(*gamepad, *button_type)));
     }
 }
 
@@ -0,0 +1,261 @@
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+use crate::{
+    arena::{Arena, Hazard, Obstacle},
+    event::RoundResetEvent,
+    mode::{ActiveGameMode, GameMode},
+    player::{Dead, Player, PlayerColors},
+    points::Points,
+    powerup::PowerUpKind,
+    prelude::*,
+    state::GameState,
+};
+
+#[derive(Default)]
+pub struct HillPlugin;
+impl Plugin for HillPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(spawn_hill_system)
+            .add_system(hill_move_system.in_set(OnUpdate(GameState::Playing)))
+            .add_system(hill_score_system.in_set(OnUpdate(GameState::Playing)))
+            .add_system(hill_reset_system);
+    }
+}
+
+/// Scoring rules for king-of-the-hill: points only come from holding the hill, and knocking
+/// someone out just gets them off of it.
+#[derive(Clone, Copy, Debug)]
+pub struct KingOfTheHillMode {
+    pub win_points: usize,
+}
+
+impl Default for KingOfTheHillMode {
+    fn default() -> Self {
+        Self {
+            win_points: HILL_WIN_POINTS,
+        }
+    }
+}
+
+impl GameMode for KingOfTheHillMode {
+    fn name(&self) -> &str {
+        "King of the Hill"
+    }
+
+    fn knockout_points(
+        &self,
+        _victim: PlayerID,
+        _point_recipient: Option<PlayerID>,
+    ) -> Option<PlayerID> {
+        None
+    }
+
+    fn round_winner(&self, points: &[Points]) -> Option<PlayerID> {
+        points
+            .iter()
+            .find(|points| points.value >= self.win_points)
+            .map(|points| points.player_id)
+    }
+
+    fn power_up_table(&self) -> Vec<(PowerUpKind, u32)> {
+        // A shield (or being too heavy to shove) would make it too easy to sit on the hill, so
+        // power-ups here are about getting people off of it
+        vec![
+            (PowerUpKind::GiantGlove, 2),
+            (PowerUpKind::Speed, 2),
+            (PowerUpKind::RapidPunch, 2),
+            (PowerUpKind::Heavy, 1),
+        ]
+    }
+
+    fn win_message(&self, player_id: PlayerID) -> String {
+        format!("Player {} is king\nof the hill!", player_id)
+    }
+
+    fn match_win_message(&self, player_id: PlayerID) -> String {
+        format!("Player {} rules\nthe hill!", player_id)
+    }
+}
+
+/// The scoring zone. Whoever stands in it alone earns points.
+#[derive(Component)]
+pub struct Hill {
+    /// Who is standing alone in the hill, if anyone
+    pub holder: Option<PlayerID>,
+    /// Whether more than one player is standing in the hill
+    pub contested: bool,
+    /// How long until the hill moves somewhere else
+    pub move_timer: Timer,
+    /// How long until the holder earns their next point
+    pub point_timer: Timer,
+}
+
+impl Hill {
+    pub fn new() -> Self {
+        Self {
+            holder: None,
+            contested: false,
+            move_timer: Timer::from_seconds(HILL_MOVE_DURATION, TimerMode::Repeating),
+            point_timer: Timer::from_seconds(HILL_POINT_DURATION, TimerMode::Repeating),
+        }
+    }
+}
+
+impl Default for Hill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Put a hill on the arena, as long as king-of-the-hill is the mode being played. The rest of the
+/// hill systems don't do anything without one.
+fn spawn_hill_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_mode: Res<ActiveGameMode>,
+) {
+    if !game_mode.is::<KingOfTheHillMode>() {
+        return;
+    }
+    commands
+        .spawn(SpriteBundle {
+            texture: asset_server.load("circle.png"),
+            transform: Transform::from_translation(Vec3::new(0.0, 0.0, LAYER_HILL))
+                .with_scale(Vec3::splat(HILL_RADIUS / COLLISION_RADIUS)),
+            sprite: Sprite {
+                color: Color::rgba(1.0, 1.0, 1.0, 0.3),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(Hill::new());
+}
+
+/// Move the hill to a random spot in the arena every so often
+fn hill_move_system(
+    time: Res<Time>,
+    mut hill_query: Query<(&mut Hill, &mut Transform), Without<Arena>>,
+    arena_query: Query<(&Arena, &Transform)>,
+    obstacle_query: Query<&Obstacle>,
+    hazard_query: Query<&Hazard>,
+) {
+    let Ok((arena, arena_transform)) = arena_query.get_single() else {
+        return;
+    };
+    let obstacles: Vec<&Obstacle> = obstacle_query.iter().collect();
+    let hazards: Vec<&Hazard> = hazard_query.iter().collect();
+    for (mut hill, mut transform) in hill_query.iter_mut() {
+        // Also move the hill right away if nobody could stand in the middle of it, like when the
+        // arena changes between rounds and the hill was placed clear of the old arena
+        let stranded = !arena.can_stand_at(
+            arena_transform,
+            transform.translation.xy(),
+            &obstacles,
+            &hazards,
+        );
+        if !hill.move_timer.tick(time.delta()).just_finished() && !stranded {
+            continue;
+        }
+        transform.translation = hill_spot(arena, arena_transform, &obstacles, &hazards);
+        hill.holder = None;
+        hill.point_timer.reset();
+    }
+}
+
+/// Figure out who is in the hill, award points to a lone holder, and color the hill to match
+fn hill_score_system(
+    time: Res<Time>,
+    colors: Res<PlayerColors>,
+    mut hill_query: Query<(&mut Hill, &Transform, &mut Sprite)>,
+    player_query: Query<(&Player, &Transform), Without<Dead>>,
+    mut points_query: Query<&mut Points>,
+) {
+    for (mut hill, hill_transform, mut sprite) in hill_query.iter_mut() {
+        let occupants: Vec<PlayerID> = player_query
+            .iter()
+            .filter(|(_, transform)| {
+                (transform.translation.xy() - hill_transform.translation.xy()).length()
+                    < HILL_RADIUS
+            })
+            .map(|(player, _)| player.id)
+            .collect();
+        let holder = if occupants.len() == 1 {
+            Some(occupants[0])
+        } else {
+            None
+        };
+        hill.contested = occupants.len() > 1;
+        if holder != hill.holder {
+            // Someone new has the hill (or nobody does), so they have to earn it from scratch
+            hill.holder = holder;
+            hill.point_timer.reset();
+        }
+
+        sprite.color = if hill.contested {
+            Color::rgba(0.9, 0.1, 0.1, 0.4)
+        } else if let Some(player_id) = hill.holder {
+            colors.get(player_id).with_a(0.5)
+        } else {
+            Color::rgba(1.0, 1.0, 1.0, 0.3)
+        };
+
+        let Some(player_id) = hill.holder else {
+            continue;
+        };
+        if !hill.point_timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        for mut points in points_query.iter_mut() {
+            if points.player_id != player_id {
+                continue;
+            }
+            points.value += 1;
+            println!(
+                "Player {} is king of the hill and now has {} points",
+                points.player_id, points.value
+            );
+        }
+    }
+}
+
+/// Move the hill somewhere new when a new round starts
+fn hill_reset_system(
+    mut round_reset_events: EventReader<RoundResetEvent>,
+    mut hill_query: Query<(&mut Hill, &mut Transform)>,
+    arena_query: Query<&Arena>,
+    obstacle_query: Query<&Obstacle>,
+    hazard_query: Query<&Hazard>,
+) {
+    if round_reset_events.iter().next().is_none() {
+        return;
+    }
+    let obstacles: Vec<&Obstacle> = obstacle_query.iter().collect();
+    let hazards: Vec<&Hazard> = hazard_query.iter().collect();
+    for (mut hill, mut transform) in hill_query.iter_mut() {
+        *hill = Hill::new();
+        // If the arena is being swapped for a new one, this is still the old arena, so
+        // hill_move_system moves the hill again if it ends up somewhere nobody can stand
+        transform.translation = match arena_query.get_single() {
+            // The arena goes back to where it started at the start of a round
+            Ok(arena) => hill_spot(arena, &arena.home_transform(), &obstacles, &hazards),
+            Err(_) => Vec3::new(0.0, 0.0, LAYER_HILL),
+        };
+    }
+}
+
+/// A random spot for the hill that is inside the arena and clear of obstacles and hazards, so it
+/// never ends up somewhere nobody can stand
+fn hill_spot(
+    arena: &Arena,
+    arena_transform: &Transform,
+    obstacles: &[&Obstacle],
+    hazards: &[&Hazard],
+) -> Vec3 {
+    // Keep the whole hill inside the arena, even if the arena has shrunk
+    let margin = HILL_RADIUS / arena_transform.scale.x;
+    arena
+        .random_clear_point(arena_transform, margin, obstacles, hazards)
+        // If the arena is too cluttered, settle for anywhere on the floor
+        .unwrap_or_else(|| arena.random_point(arena_transform, margin))
+        .extend(LAYER_HILL)
+}
@@ -1,59 +1,221 @@
 pub mod arena;
 pub mod event;
 pub mod gamepad;
+pub mod hill;
+pub mod mode;
 pub mod player;
 pub mod points;
+pub mod powerup;
+pub mod projectile;
+pub mod state;
+pub mod tournament;
 
 mod prelude {
     /// An alias to show that we're dealing with a player id
     pub type PlayerID = usize;
-    /// Radius of the arena circle -- based off of circle radius in the PNG
+    /// Where arena definition files live, relative to the folder the asset server finds `assets` in
+    pub const ARENA_DIRECTORY: &str = "assets/arenas";
+    /// Radius of the default arena circle at full size -- based off of circle radius in the PNG
     pub const ARENA_RADIUS: f32 = 384.0;
+    /// How long a thrown ball rolls around before disappearing
+    pub const BALL_LIFETIME: f32 = 5.0;
+    /// How heavy a thrown ball is, compared to a player
+    pub const BALL_MASS: f32 = 0.5;
+    /// The radius of a thrown ball
+    pub const BALL_RADIUS: f32 = 16.0;
+    /// How bouncy a thrown ball is, from 0.0 to 1.0
+    pub const BALL_RESTITUTION: f32 = 1.0;
+    /// How fast a ball leaves the player who throws it
+    pub const BALL_THROW_SPEED: f32 = MAX_VELOCITY * 3.0;
+    /// How far around from straight ahead a blocking player's guard covers, as the cosine of the
+    /// angle (so 0.0 covers the whole front half)
+    pub const BLOCK_ARC_COS: f32 = 0.0;
+    /// How fast a player can move while blocking, as a fraction of their normal speed
+    pub const BLOCK_MOVE_SCALE: f32 = 0.5;
+    /// How much of a punch's pushback still gets through a block
+    pub const BLOCK_PUSHBACK_SCALE: f32 = 0.2;
+    /// In a tournament, how long the bracket is shown between matches if nobody presses A
+    pub const BRACKET_DISPLAY_DURATION: f32 = 30.0;
+    /// Size of the grid cells used to find players that might be colliding. Anything a bit bigger
+    /// than a player works; much bigger and too many players share a cell.
+    pub const BROADPHASE_CELL_SIZE: f32 = 128.0;
+    /// How much players get pushed back when their gloves clash
+    pub const CLASH_PUSHBACK: f32 = 1.5;
+    /// How long players are stunned when their gloves clash
+    pub const CLASH_STUN_DURATION: f32 = 0.25;
     /// The radius of a player sprite, used for collision detection
     pub const COLLISION_RADIUS: f32 = 32.0;
+    /// How much of the remaining overlap between players gets pushed apart each frame
+    pub const CONTACT_CORRECTION: f32 = 0.8;
+    /// How many passes the collision solver makes over all the contacts each frame
+    pub const CONTACT_ITERATIONS: usize = 8;
+    /// How much players can overlap before they get pushed apart, to keep them from jittering
+    pub const CONTACT_SLOP: f32 = 0.5;
+    /// With the damage meter on, how much farther punches knock a player back per percent of damage
+    /// they've taken (so at 100% they go twice as far)
+    pub const DAMAGE_KNOCKBACK_SCALE: f32 = 0.01;
+    /// How far above the center of a player their damage meter sits
+    pub const DAMAGE_METER_OFFSET: f32 = COLLISION_RADIUS + 16.0;
+    /// With the damage meter on, how many percent of damage a punch deals per unit of pushback
+    pub const DAMAGE_PER_PUSHBACK: f32 = 3.0;
+    /// How long a player has to wait between dashes
+    pub const DASH_COOLDOWN: f32 = 1.5;
+    /// How long a dash lasts, during which the dashing player can't be hit
+    pub const DASH_DURATION: f32 = 0.15;
+    /// How far below the center of a player their dash meter sits
+    pub const DASH_METER_OFFSET: f32 = COLLISION_RADIUS + 8.0;
+    /// How fast a player moves while dashing
+    pub const DASH_SPEED: f32 = MAX_VELOCITY * 3.5;
     /// How far a joystick has to move before it's no longer considered neutral
     pub const DEAD_ZONE_THRESHOLD: f32 = 0.2;
+    /// Which arena to play on if none is picked, from `ARENA_DIRECTORY`
+    pub const DEFAULT_ARENA: &str = "classic";
+    /// Where players 0, 1, 2, and 3 spawn if there's no arena to tell us otherwise
+    pub const DEFAULT_SPAWN_POINTS: [[f32; 2]; 4] = [
+        [-100.0, 100.0],
+        [100.0, 100.0],
+        [100.0, -100.0],
+        [-100.0, -100.0],
+    ];
     /// How quickly movement should slow to a stop when joystick is neutral
     pub const DRAG: f32 = 0.8;
+    /// How much farther a giant glove power-up punches
+    pub const GIANT_GLOVE_SCALE: f32 = 2.0;
+    /// The radius of a boxing glove, used to tell when a punch hits someone
+    pub const GLOVE_RADIUS: f32 = 32.0;
+    /// How long a fired glove flies before dropping out of play
+    pub const GLOVE_SHOT_LIFETIME: f32 = 1.0;
+    /// How heavy a fired glove is, compared to a player
+    pub const GLOVE_SHOT_MASS: f32 = 1.0;
+    /// How bouncy a fired glove is, from 0.0 to 1.0
+    pub const GLOVE_SHOT_RESTITUTION: f32 = 0.3;
+    /// How fast a fired glove leaves the player who fires it
+    pub const GLOVE_SHOT_SPEED: f32 = MAX_VELOCITY * 4.0;
+    /// How many times a held player has to mash a button to break free
+    pub const GRAB_BREAK_MASHES: u32 = 8;
+    /// How long a player is stunned after someone breaks free of their grab
+    pub const GRAB_BREAK_STUN_DURATION: f32 = 0.5;
+    /// How long a grab holds on before the held player gets thrown
+    pub const GRAB_DURATION: f32 = 0.8;
+    /// How close another player has to be (center to center) to grab them
+    pub const GRAB_RANGE: f32 = COLLISION_RADIUS * 2.0 + 16.0;
+    /// How fast a thrown player flies
+    pub const GRAB_THROW_SPEED: f32 = MAX_VELOCITY * 3.5;
+    /// How many times heavier the heavy power-up makes a player
+    pub const HEAVY_MASS_SCALE: f32 = 2.0;
+    /// In king-of-the-hill mode, how long the hill stays put before moving somewhere else
+    pub const HILL_MOVE_DURATION: f32 = 15.0;
+    /// In king-of-the-hill mode, how long you have to hold the hill by yourself to earn a point
+    pub const HILL_POINT_DURATION: f32 = 2.0;
+    /// In king-of-the-hill mode, the radius of the hill
+    pub const HILL_RADIUS: f32 = 96.0;
+    /// In king-of-the-hill mode, how many points it takes to win a round
+    pub const HILL_WIN_POINTS: usize = 15;
+    /// How much shorter each hit in a combo stuns for than the hit before it
+    pub const HIT_STUN_COMBO_SCALE: f32 = 0.8;
+    /// How long a punch stuns whoever it hits, per unit of pushback it lands with
+    pub const HIT_STUN_PER_PUSHBACK: f32 = 0.1;
     /// Z depth for the arena
     pub const LAYER_ARENA: f32 = 0.0;
+    /// Z depth for the tournament bracket screen, in front of everything else
+    pub const LAYER_BRACKET: f32 = 0.5;
     /// Z depth for gloves
     pub const LAYER_GLOVE: f32 = 0.2;
+    /// Z depth for hazards on the arena floor
+    pub const LAYER_HAZARD: f32 = 0.01;
+    /// Z depth for the king-of-the-hill zone
+    pub const LAYER_HILL: f32 = 0.05;
+    /// Z depth for obstacles on the arena floor
+    pub const LAYER_OBSTACLE: f32 = 0.02;
     /// Z depth for players (positive Z is towards the viewer, negative Z is into the screen)
     pub const LAYER_PLAYER: f32 = 0.1;
     /// Z depth for points
     pub const LAYER_POINTS: f32 = 0.3;
+    /// Z depth for power-up pickups on the arena floor
+    pub const LAYER_POWER_UP: f32 = 0.06;
+    /// Z depth for thrown projectiles
+    pub const LAYER_PROJECTILE: f32 = 0.15;
     /// Maximum velocity a player can move by itself (can be exceeded when punched)
     pub const MAX_VELOCITY: f32 = 6.0;
     /// How fast a player accelerates
     pub const MOVE_SPEED: f32 = 25.0;
+    /// How much of a player's speed is kept when bouncing off of an obstacle
+    pub const OBSTACLE_RESTITUTION: f32 = 0.8;
+    /// How far out a glove still has to be for punching into it to count as a parry, from 0.0
+    /// (drawn all the way back) to 1.0 (fully extended)
+    pub const PARRY_MIN_STRENGTH: f32 = 0.5;
+    /// How long a player is stunned when their punch gets parried
+    pub const PARRY_STUN_DURATION: f32 = 0.75;
+    /// How much a player weighs by default -- collisions and punches push heavier players around
+    /// less
+    pub const PLAYER_MASS: f32 = 1.0;
+    /// How bouncy players are by default when they bump into each other, from 0.0 to 1.0
+    pub const PLAYER_RESTITUTION: f32 = 0.9;
     /// How long after being the last to touch someone you will get a point if they leave the arena
     pub const POINT_TOUCH_DURATION: f32 = 5.0;
+    /// How long a power-up lasts once it's picked up
+    pub const POWER_UP_DURATION: f32 = 8.0;
+    /// The most power-up pickups that can be lying around the arena at once
+    pub const POWER_UP_MAX_PICKUPS: usize = 2;
+    /// The radius of a power-up pickup
+    pub const POWER_UP_RADIUS: f32 = 16.0;
+    /// With power-ups turned on, how often a new pickup appears
+    pub const POWER_UP_SPAWN_INTERVAL: f32 = 10.0;
+    /// With projectiles turned on, how long a player has to wait between throws
+    pub const PROJECTILE_COOLDOWN: f32 = 2.0;
+    /// How quickly projectiles slow down as they fly
+    pub const PROJECTILE_DRAG: f32 = 0.3;
     /// How far away from the center of the player that the boxing glove rests
     pub const PUNCH_BASE: f32 = 40.0;
     /// Where the boxing glove rests relative to the player as it's parent as an array (convert it to Vec3)
     pub const PUNCH_BASE_ARR3: [f32; 3] = [PUNCH_BASE, 0.0, 0.1];
+    /// How long the punch trigger has to be held to fully charge a punch
+    pub const PUNCH_CHARGE_DURATION: f32 = 1.0;
+    /// How fast a player can move while charging a punch, as a fraction of their normal speed
+    pub const PUNCH_CHARGE_MOVE_SCALE: f32 = 0.4;
     /// How long it takes to draw your boxing glove back after a punch
     pub const PUNCH_DRAWBACK_DURATION: f32 = 0.50;
-    /// Boxing glove location relative to player parent when fully extended
-    pub const PUNCH_EXTENDED_ARR3: [f32; 3] = [PUNCH_BASE + PUNCH_LENGTH, 0.0, LAYER_GLOVE];
     /// How far the boxing glove punches outward
     pub const PUNCH_LENGTH: f32 = 50.0;
+    /// How far out a glove still has to be for it to hit anyone, from 0.0 (drawn all the way back)
+    /// to 1.0 (fully extended)
+    pub const PUNCH_MIN_STRENGTH: f32 = 0.25;
+    /// How much a fully charged punch pushes back the punched player
+    pub const PUNCH_PUSHBACK_CHARGED: f32 = 6.0;
     /// How much the punched player gets pushed back
     pub const PUNCH_PUSHBACK_OTHER: f32 = 3.0;
     /// How much the player doing the punching gets pushed back
     pub const PUNCH_PUSHBACK_SELF: f32 = 1.0;
+    /// How far the boxing glove winds back behind where it rests when a punch is fully charged
+    pub const PUNCH_WINDUP: f32 = 20.0;
+    /// How much the rapid punch power-up shortens the time it takes to draw your glove back
+    pub const RAPID_PUNCH_SCALE: f32 = 0.5;
+    /// How many random spots to try when looking for somewhere clear of obstacles and hazards
+    pub const RANDOM_POINT_ATTEMPTS: usize = 20;
     /// How long it takes to shrink and respawn once you've died
     pub const RESPAWN_DURATION: f32 = 1.5;
-    /// Where players 0, 1, 2, and 3 spawn on the screen.
-    pub const STARTING_LOCATIONS: [[f32; 3]; 4] = [
-        [-100.0, 100.0, LAYER_PLAYER],
-        [100.0, 100.0, LAYER_PLAYER],
-        [100.0, -100.0, LAYER_PLAYER],
-        [-100.0, -100.0, LAYER_PLAYER],
-    ];
+    /// How many rounds it takes to win a match by default
+    pub const ROUNDS_TO_WIN: usize = 2;
+    /// In shrinking arena mode, how many seconds into a round the arena reaches its smallest size
+    pub const SHRINK_END: f32 = 90.0;
+    /// In shrinking arena mode, the smallest the arena gets as a fraction of its full size
+    pub const SHRINK_MIN_SCALE: f32 = 0.45;
+    /// In shrinking arena mode, how many seconds into a round the arena starts to shrink
+    pub const SHRINK_START: f32 = 30.0;
+    /// How much faster the speed boost power-up lets a player move
+    pub const SPEED_BOOST_SCALE: f32 = 1.5;
+    /// With teetering turned on, how long a player can teeter on the edge before falling off
+    pub const TEETER_DURATION: f32 = 1.0;
+    /// With teetering turned on, the fastest a player can go over the edge and still teeter (so a
+    /// punch knocks you right off, but walking or getting bumped doesn't)
+    pub const TEETER_MAX_VELOCITY: f32 = MAX_VELOCITY * 1.25;
+    /// How directly a teetering player has to push back toward the arena to recover, from 0.0
+    /// (anywhere inward) to 1.0 (straight at it)
+    pub const TEETER_RECOVERY_THRESHOLD: f32 = 0.5;
+    /// Where a tournament in progress is saved, so it survives a restart
+    pub const TOURNAMENT_FILE: &str = "tournament.ron";
     /// How fast a player turns
     pub const TURN_SPEED: f32 = 6.0;
-    /// How many points it takes to win
+    /// How many knockouts it takes to win a round in the default mode
     pub const WIN_POINTS: usize = 10;
 }
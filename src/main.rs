@@ -1,13 +1,90 @@
 use bevy::{prelude::*, window::WindowResolution};
 //use bevy_rapier2d::render::RapierRenderPlugin;
 use punchball::{
-    arena::ArenaPlugin, event::EventPlugin, gamepad::GamepadPlugin, player::PlayerPlugin,
-    points::PointsPlugin,
+    arena::{ArenaPlugin, ArenaRotation, ArenaSettings, ArenaShrink},
+    event::EventPlugin,
+    gamepad::GamepadPlugin,
+    hill::{HillPlugin, KingOfTheHillMode},
+    mode::ActiveGameMode,
+    player::{Damage, PlayerPlugin, Teeter},
+    points::{MatchScore, PointsPlugin},
+    powerup::{PowerUpPlugin, PowerUps},
+    projectile::{ProjectilePlugin, Projectiles},
+    state::StatePlugin,
+    tournament::{Elimination, Tournament, TournamentPlugin},
 };
 
 fn main() {
-    App::new()
-        .insert_resource(ClearColor(Color::rgb(0.0, 0.0, 0.0)))
+    let shrinking_arena = has_arg("--shrinking-arena");
+    let mut arena_settings = ArenaSettings {
+        // If nobody said which arena to play on, let them pick one
+        pick: !has_arg("--arena"),
+        rotation: arg_value("--arena-rotation")
+            .and_then(|name| ArenaRotation::from_name(&name))
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+    if let Some(arena) = arg_value("--arena") {
+        arena_settings.arena = arena;
+    }
+    let king_of_the_hill = has_arg("--king-of-the-hill");
+    let teeter = has_arg("--teeter");
+    let damage = has_arg("--damage");
+    let power_ups = has_arg("--power-ups");
+    let projectiles = has_arg("--projectiles");
+    let match_score = arg_value("--best-of")
+        .and_then(|rounds| rounds.parse().ok())
+        .map_or_else(MatchScore::default, MatchScore::best_of);
+    let tournament_roster: Option<Vec<String>> = has_arg("--tournament").then(|| {
+        arg_value("--tournament")
+            .map(|names| {
+                names
+                    .split(',')
+                    .map(|name| name.trim().to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    });
+    let elimination = if has_arg("--double-elimination") {
+        Elimination::Double
+    } else {
+        Elimination::Single
+    };
+    let game_mode = if king_of_the_hill {
+        ActiveGameMode::new(KingOfTheHillMode::default())
+    } else {
+        ActiveGameMode::default()
+    };
+    let mut app = App::new();
+    if let Some(roster) = tournament_roster {
+        match Tournament::new(roster, elimination) {
+            Some(tournament) => {
+                app.insert_resource(tournament);
+            }
+            None => println!(
+                "A tournament needs at least two names, none blank, like --tournament Ann,Bob,Cat"
+            ),
+        }
+    }
+    app.insert_resource(ClearColor(Color::rgb(0.0, 0.0, 0.0)))
+        .insert_resource(game_mode)
+        .insert_resource(match_score)
+        .insert_resource(arena_settings)
+        .insert_resource(if shrinking_arena {
+            ArenaShrink::enabled()
+        } else {
+            ArenaShrink::default()
+        })
+        .insert_resource(Teeter { enabled: teeter })
+        .insert_resource(Damage { enabled: damage })
+        .insert_resource(if power_ups {
+            PowerUps::enabled()
+        } else {
+            PowerUps::default()
+        })
+        .insert_resource(Projectiles {
+            enabled: projectiles,
+        })
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: "Punch Ball".to_string(),
@@ -20,12 +97,30 @@ fn main() {
         .add_plugin(ArenaPlugin::default())
         .add_plugin(EventPlugin::default())
         .add_plugin(GamepadPlugin::default())
+        .add_plugin(HillPlugin::default())
         .add_plugin(PlayerPlugin::default())
         .add_plugin(PointsPlugin::default())
+        .add_plugin(PowerUpPlugin::default())
+        .add_plugin(ProjectilePlugin::default())
+        .add_plugin(StatePlugin::default())
+        .add_plugin(TournamentPlugin::default())
         .add_startup_system(setup)
         .run();
 }
 
+/// Whether a command-line flag was passed
+fn has_arg(name: &str) -> bool {
+    std::env::args().any(|arg| arg == name)
+}
+
+/// The value following a command-line flag, if there is one
+fn arg_value(name: &str) -> Option<String> {
+    std::env::args()
+        .skip_while(|arg| arg != name)
+        .nth(1)
+        .filter(|value| !value.starts_with("--"))
+}
+
 fn setup(mut commands: Commands) {
     commands.spawn(Camera2dBundle::default());
 }
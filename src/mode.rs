@@ -0,0 +1,115 @@
+use std::any::TypeId;
+
+use bevy::prelude::*;
+
+use crate::{points::Points, powerup::PowerUpKind, prelude::*};
+
+/// The rules of a game mode: who gets points for a knockout, when a round is over and who won it,
+/// and what the HUD shows. Rounds are strung together into a match by `MatchScore`. Modes that
+/// need their own entities or systems (like king-of-the-hill) add those with a plugin of their
+/// own, which checks `ActiveGameMode::is` so it only sets things up when its mode is in play, and
+/// then use this trait for the scoring rules.
+pub trait GameMode: Send + Sync + 'static {
+    /// Short name of the mode, for messages
+    fn name(&self) -> &str;
+
+    /// Who gets a point when `victim` leaves the arena. `point_recipient` is the last player to
+    /// touch the victim recently, if any.
+    fn knockout_points(
+        &self,
+        victim: PlayerID,
+        point_recipient: Option<PlayerID>,
+    ) -> Option<PlayerID> {
+        let _ = victim;
+        point_recipient
+    }
+
+    /// Called whenever points change. Return the winner to end the round.
+    fn round_winner(&self, points: &[Points]) -> Option<PlayerID>;
+
+    /// Which power-ups can show up when they're turned on, each with a weight for how often it's
+    /// picked relative to the others. An empty table means no power-ups at all.
+    fn power_up_table(&self) -> Vec<(PowerUpKind, u32)> {
+        PowerUpKind::ALL.iter().map(|&kind| (kind, 1)).collect()
+    }
+
+    /// What to show in a player's HUD slot
+    fn hud_text(&self, points: &Points) -> String {
+        format!("{}", points.value)
+    }
+
+    /// What to show when someone wins a round
+    fn win_message(&self, player_id: PlayerID) -> String {
+        format!("Player {} wins\nthe round!", player_id)
+    }
+
+    /// What to show when someone wins the whole match
+    fn match_win_message(&self, player_id: PlayerID) -> String {
+        format!("Player {} wins\nthe match!", player_id)
+    }
+}
+
+/// The game mode currently in play, and what type it is
+#[derive(Resource)]
+pub struct ActiveGameMode(pub Box<dyn GameMode>, TypeId);
+
+impl ActiveGameMode {
+    pub fn new<M: GameMode>(mode: M) -> Self {
+        Self(Box::new(mode), TypeId::of::<M>())
+    }
+
+    /// Whether the mode in play is an `M`
+    pub fn is<M: GameMode>(&self) -> bool {
+        self.1 == TypeId::of::<M>()
+    }
+}
+
+impl Default for ActiveGameMode {
+    fn default() -> Self {
+        Self::new(KnockoutMode::default())
+    }
+}
+
+/// The classic mode: knock people out of the arena, and the first to `win_points` knockouts wins.
+/// The knockout is credited to the last player who touched the victim.
+#[derive(Clone, Copy, Debug)]
+pub struct KnockoutMode {
+    pub win_points: usize,
+}
+
+impl Default for KnockoutMode {
+    fn default() -> Self {
+        Self {
+            win_points: WIN_POINTS,
+        }
+    }
+}
+
+impl GameMode for KnockoutMode {
+    fn name(&self) -> &str {
+        "Knockout"
+    }
+
+    fn round_winner(&self, points: &[Points]) -> Option<PlayerID> {
+        points
+            .iter()
+            .find(|points| points.value >= self.win_points)
+            .map(|points| points.player_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hill::KingOfTheHillMode;
+
+    #[test]
+    fn active_game_mode_knows_its_type() {
+        let knockout = ActiveGameMode::default();
+        assert!(knockout.is::<KnockoutMode>());
+        assert!(!knockout.is::<KingOfTheHillMode>());
+        let hill = ActiveGameMode::new(KingOfTheHillMode::default());
+        assert!(hill.is::<KingOfTheHillMode>());
+        assert!(!hill.is::<KnockoutMode>());
+    }
+}
@@ -1,61 +1,360 @@
-use crate::prelude::PlayerID;
-use bevy::prelude::*;
-
-/// A collision between two players
-#[derive(Copy, Clone, Default)]
-pub(crate) struct Collision {
-    pub(crate) player_id1: PlayerID,
-    pub(crate) player_id2: PlayerID,
-    pub(crate) pos1: Vec2,
-    pub(crate) pos2: Vec2,
-    pub(crate) vel1: Vec2,
-    pub(crate) vel2: Vec2,
-}
-
-impl Collision {
-    /// Ask the collision if you're involved, if you are, what's your new velocity?
-    pub(crate) fn new_velocity(&self, player_id: PlayerID) -> Option<Vec2> {
-        // From the bottom of https://en.wikipedia.org/wiki/Elastic_collision assuming equal masses
-        if player_id == self.player_id1 {
-            Some(
-                self.vel1
-                    - ((self.vel1 - self.vel2).dot(self.pos1 - self.pos2)
-                        / (self.pos1 - self.pos2).length_squared())
-                        * (self.pos1 - self.pos2),
-            )
-        } else if player_id == self.player_id2 {
-            Some(
-                self.vel2
-                    - ((self.vel2 - self.vel1).dot(self.pos2 - self.pos1)
-                        / (self.pos2 - self.pos1).length_squared())
-                        * (self.pos2 - self.pos1),
-            )
+use crate::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
+
+/// What a body stands for in the game
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BodyId {
+    Player(PlayerID),
+    Projectile(Entity),
+}
+
+/// A player or projectile as far as the collision solver is concerned: a circle with a position,
+/// velocity, and mass
+#[derive(Copy, Clone, Debug)]
+pub struct Body {
+    pub id: BodyId,
+    pub pos: Vec2,
+    pub vel: Vec2,
+    pub radius: f32,
+    /// One over the body's mass, so that infinitely heavy bodies can be represented with zero
+    pub inv_mass: f32,
+    pub restitution: f32,
+}
+
+/// Two bodies that are touching, or will be by the end of the step
+#[derive(Copy, Clone, Debug)]
+pub struct Contact {
+    /// Index of the first body
+    pub a: usize,
+    /// Index of the second body
+    pub b: usize,
+    /// Direction from the first body to the second
+    pub normal: Vec2,
+    /// How fast the bodies should separate along the normal once the contact is resolved. This is
+    /// negative for bodies that haven't met yet, so they can close the gap but no more.
+    target_speed: f32,
+    /// Total impulse applied so far, so the solver can never end up pulling the bodies together
+    impulse: f32,
+}
+
+/// The earliest point during a step, from 0.0 (the start) to 1.0 (the end), at which something
+/// `offset` away from the origin and moving by `motion` over the step comes within `distance` of
+/// the origin. Testing the whole path like this means fast movers can't skip past each other
+/// between frames.
+pub fn time_of_impact(offset: Vec2, motion: Vec2, distance: f32) -> Option<f32> {
+    let c = offset.length_squared() - distance * distance;
+    if c < 0.0 {
+        // Already touching
+        return Some(0.0);
+    }
+    let a = motion.length_squared();
+    let b = 2.0 * offset.dot(motion);
+    if a <= 0.0 || b >= 0.0 {
+        // Standing still or moving apart
+        return None;
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        // Passing by without touching
+        return None;
+    }
+    let t = (-b - discriminant.sqrt()) / (2.0 * a);
+    (t <= 1.0).then_some(t)
+}
+
+impl Contact {
+    /// If two bodies overlap, or will run into each other over the next `step` seconds (scaled the
+    /// same way as velocity), the contact between them
+    pub fn between(bodies: &[Body], a: usize, b: usize, step: f32) -> Option<Self> {
+        let offset = bodies[b].pos - bodies[a].pos;
+        let motion = (bodies[b].vel - bodies[a].vel) * step;
+        let t = time_of_impact(offset, motion, bodies[a].radius + bodies[b].radius)?;
+        // The normal is taken where the bodies first touch. Bodies sitting right on top of each
+        // other get pushed apart in an arbitrary direction.
+        let normal = (offset + motion * t).try_normalize().unwrap_or(Vec2::X);
+        let gap = offset.dot(normal) - (bodies[a].radius + bodies[b].radius);
+        let approach_speed = (bodies[b].vel - bodies[a].vel).dot(normal);
+        let restitution = bodies[a].restitution.min(bodies[b].restitution);
+        let target_speed = if gap > 0.0 {
+            // Not touching yet, so they only get slowed down enough to meet by the end of the step,
+            // and don't bounce off each other from a distance
+            -gap / step
+        } else if approach_speed < 0.0 {
+            -approach_speed * restitution
         } else {
-            None
-        }
+            0.0
+        };
+        Some(Self {
+            a,
+            b,
+            normal,
+            target_speed,
+            impulse: 0.0,
+        })
     }
 }
 
-/// Make it so that a collision involving two players is equal to any other collision involving the two players
-impl PartialEq for Collision {
-    fn eq(&self, other: &Self) -> bool {
-        ((self.player_id1 == other.player_id1) && (self.player_id2 == other.player_id2))
-            || ((self.player_id1 == other.player_id2) && (self.player_id2 == other.player_id1))
+/// The box around everywhere a body goes over the next `step`
+fn swept_bounds(body: &Body, step: f32) -> (Vec2, Vec2) {
+    let end = body.pos + body.vel * step;
+    (
+        body.pos.min(end) - Vec2::splat(body.radius),
+        body.pos.max(end) + Vec2::splat(body.radius),
+    )
+}
+
+/// Which broadphase grid cell a point falls in
+fn grid_cell(point: Vec2) -> IVec2 {
+    (point / BROADPHASE_CELL_SIZE).floor().as_ivec2()
+}
+
+/// Pairs of bodies (by index, lowest first) that might collide over the next `step`. Bodies are
+/// sorted into a uniform grid, so only bodies that share a grid cell get compared, and each pair
+/// comes out exactly once.
+pub fn candidate_pairs(bodies: &[Body], step: f32) -> Vec<(usize, usize)> {
+    let bounds: Vec<(Vec2, Vec2)> = bodies.iter().map(|body| swept_bounds(body, step)).collect();
+    let mut grid: HashMap<IVec2, Vec<usize>> = HashMap::new();
+    for (index, &(min, max)) in bounds.iter().enumerate() {
+        let (min_cell, max_cell) = (grid_cell(min), grid_cell(max));
+        for x in min_cell.x..=max_cell.x {
+            for y in min_cell.y..=max_cell.y {
+                grid.entry(IVec2::new(x, y)).or_default().push(index);
+            }
+        }
     }
+    let mut pairs = Vec::new();
+    for (&cell, members) in grid.iter() {
+        for (i, &a) in members.iter().enumerate() {
+            for &b in &members[i + 1..] {
+                let (min_a, max_a) = bounds[a];
+                let (min_b, max_b) = bounds[b];
+                let (overlap_min, overlap_max) = (min_a.max(min_b), max_a.min(max_b));
+                if overlap_min.x > overlap_max.x || overlap_min.y > overlap_max.y {
+                    continue;
+                }
+                // Bodies that share several cells only get paired up in the first one
+                if grid_cell(overlap_min) != cell {
+                    continue;
+                }
+                pairs.push((a.min(b), a.max(b)));
+            }
+        }
+    }
+    // The grid comes out in no particular order, but the solver should see the same contacts in
+    // the same order every time
+    pairs.sort_unstable();
+    pairs
+}
+
+/// Find every pair of bodies that overlap, or will collide over the next `step`
+pub fn find_contacts(bodies: &[Body], step: f32) -> Vec<Contact> {
+    candidate_pairs(bodies, step)
+        .into_iter()
+        .filter_map(|(a, b)| Contact::between(bodies, a, b, step))
+        .collect()
 }
 
-impl Eq for Collision {
-    fn assert_receiver_is_total_eq(&self) {}
+/// Bounce bodies off of each other with impulses, then push apart any that still overlap. Every
+/// contact is solved together over several passes, so a player caught between two others (or in
+/// a pile of them) gets a sensible result instead of whatever the last pair said.
+pub fn resolve_contacts(bodies: &mut [Body], contacts: &mut [Contact]) {
+    for _ in 0..CONTACT_ITERATIONS {
+        for contact in contacts.iter_mut() {
+            let (a, b) = (bodies[contact.a], bodies[contact.b]);
+            let inv_mass = a.inv_mass + b.inv_mass;
+            if inv_mass <= 0.0 {
+                continue;
+            }
+            let speed = (b.vel - a.vel).dot(contact.normal);
+            let total = (contact.impulse + (contact.target_speed - speed) / inv_mass).max(0.0);
+            let impulse = total - contact.impulse;
+            contact.impulse = total;
+            bodies[contact.a].vel -= contact.normal * impulse * a.inv_mass;
+            bodies[contact.b].vel += contact.normal * impulse * b.inv_mass;
+        }
+    }
+    for _ in 0..CONTACT_ITERATIONS {
+        for contact in contacts.iter() {
+            let (a, b) = (bodies[contact.a], bodies[contact.b]);
+            let inv_mass = a.inv_mass + b.inv_mass;
+            if inv_mass <= 0.0 {
+                continue;
+            }
+            let depth = a.radius + b.radius - (b.pos - a.pos).dot(contact.normal);
+            let correction = (depth - CONTACT_SLOP).max(0.0) * CONTACT_CORRECTION / inv_mass;
+            bodies[contact.a].pos -= contact.normal * correction * a.inv_mass;
+            bodies[contact.b].pos += contact.normal * correction * b.inv_mass;
+        }
+    }
 }
 
-/// Make it so that a collision involving two players hashes the same as any other collision with
-/// the same two players, so a HashSet will naturally deduplicate opposing pairs of collisions
-impl std::hash::Hash for Collision {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        let mut player_ids = vec![self.player_id1, self.player_id2];
-        player_ids.sort_unstable();
-        for player_id in player_ids {
-            player_id.hash(state);
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    fn body(index: usize, pos: Vec2, vel: Vec2) -> Body {
+        Body {
+            id: BodyId::Player(index),
+            pos,
+            vel,
+            radius: 32.0,
+            inv_mass: 1.0,
+            restitution: 1.0,
+        }
+    }
+
+    /// Run one step of the solver, then move the bodies along like the physics system does
+    fn step(bodies: &mut [Body], step: f32) -> Vec<Contact> {
+        let mut contacts = find_contacts(bodies, step);
+        resolve_contacts(bodies, &mut contacts);
+        for body in bodies.iter_mut() {
+            body.pos += body.vel * step;
+        }
+        contacts
+    }
+
+    #[test]
+    fn time_of_impact_head_on() {
+        // 100 apart, closing by 100, touching once they're 50 apart
+        let t = time_of_impact(Vec2::new(100.0, 0.0), Vec2::new(-100.0, 0.0), 50.0).unwrap();
+        assert!((t - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn time_of_impact_grazing() {
+        let offset = Vec2::new(100.0, 40.0);
+        let motion = Vec2::new(-200.0, 0.0);
+        assert!(time_of_impact(offset, motion, 39.0).is_none());
+        let t = time_of_impact(offset, motion, 41.0).unwrap();
+        assert!(t > 0.0 && t < 1.0);
+        assert!(((offset + motion * t).length() - 41.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn time_of_impact_already_overlapping() {
+        assert_eq!(
+            time_of_impact(Vec2::new(10.0, 0.0), Vec2::new(10.0, 0.0), 50.0),
+            Some(0.0)
+        );
+    }
+
+    #[test]
+    fn time_of_impact_zero_motion() {
+        assert!(time_of_impact(Vec2::new(100.0, 0.0), Vec2::ZERO, 50.0).is_none());
+    }
+
+    #[test]
+    fn time_of_impact_too_far_this_step() {
+        assert!(time_of_impact(Vec2::new(200.0, 0.0), Vec2::new(-100.0, 0.0), 50.0).is_none());
+    }
+
+    #[test]
+    fn head_on_collision_meets_without_bouncing_early() {
+        // Closing at 40 a step with a gap of 20, so they should meet halfway through the step
+        let mut bodies = [
+            body(0, Vec2::new(0.0, 0.0), Vec2::new(20.0, 0.0)),
+            body(1, Vec2::new(84.0, 0.0), Vec2::new(-20.0, 0.0)),
+        ];
+        let contacts = step(&mut bodies, 1.0);
+        assert_eq!(contacts.len(), 1);
+        let distance = bodies[1].pos.x - bodies[0].pos.x;
+        assert!((distance - 64.0).abs() < 1e-3, "distance was {distance}");
+        // Still heading for each other, so they bounce next step instead of from a distance
+        assert!(bodies[1].vel.x - bodies[0].vel.x < 0.0);
+    }
+
+    #[test]
+    fn head_on_collision_bounces_once_touching() {
+        let mut bodies = [
+            body(0, Vec2::new(0.0, 0.0), Vec2::new(20.0, 0.0)),
+            body(1, Vec2::new(64.0, 0.0), Vec2::new(-20.0, 0.0)),
+        ];
+        step(&mut bodies, 1.0);
+        // Equal masses with full restitution swap velocities
+        assert!((bodies[0].vel.x + 20.0).abs() < 1e-3);
+        assert!((bodies[1].vel.x - 20.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn grazing_collision_keeps_sideways_motion() {
+        let mut bodies = [
+            body(0, Vec2::new(0.0, 0.0), Vec2::ZERO),
+            body(1, Vec2::new(60.0, 40.0), Vec2::new(-40.0, 0.0)),
+        ];
+        let contacts = step(&mut bodies, 1.0);
+        assert_eq!(contacts.len(), 1);
+        assert!((bodies[1].pos - bodies[0].pos).length() >= 64.0 - CONTACT_SLOP);
+        // Momentum is conserved
+        assert!((bodies[0].vel + bodies[1].vel - Vec2::new(-40.0, 0.0)).length() < 1e-3);
+    }
+
+    #[test]
+    fn overlapping_bodies_get_pushed_apart() {
+        let mut bodies = [
+            body(0, Vec2::new(0.0, 0.0), Vec2::ZERO),
+            body(1, Vec2::new(32.0, 0.0), Vec2::ZERO),
+        ];
+        let before = bodies[1].pos.x - bodies[0].pos.x;
+        for _ in 0..10 {
+            step(&mut bodies, 1.0);
+        }
+        let after = bodies[1].pos.x - bodies[0].pos.x;
+        assert!(after > before);
+        assert!(after >= 64.0 - CONTACT_SLOP * 2.0, "distance was {after}");
+        assert_eq!(bodies[0].pos.y, 0.0);
+    }
+
+    #[test]
+    fn bodies_standing_still_apart_dont_collide() {
+        let mut bodies = [
+            body(0, Vec2::new(0.0, 0.0), Vec2::ZERO),
+            body(1, Vec2::new(100.0, 0.0), Vec2::ZERO),
+        ];
+        assert!(step(&mut bodies, 1.0).is_empty());
+        assert_eq!(bodies[0].vel, Vec2::ZERO);
+        assert_eq!(bodies[1].vel, Vec2::ZERO);
+    }
+
+    #[test]
+    fn candidate_pairs_match_brute_force() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for count in [2, 10, 100, 500] {
+            // Crowded enough that lots of bodies touch and straddle grid cells, with a few fast
+            // movers and a few big bodies that span several cells
+            let half_size = 64.0 * (count as f32).sqrt();
+            let bodies: Vec<Body> = (0..count)
+                .map(|index| Body {
+                    radius: if index % 10 == 0 { 150.0 } else { 32.0 },
+                    ..body(
+                        index,
+                        Vec2::new(
+                            rng.gen_range(-half_size..half_size),
+                            rng.gen_range(-half_size..half_size),
+                        ),
+                        Vec2::new(rng.gen_range(-60.0..60.0), rng.gen_range(-60.0..60.0)),
+                    )
+                })
+                .collect();
+            let mut brute_force = Vec::new();
+            for a in 0..bodies.len() {
+                for b in a + 1..bodies.len() {
+                    if Contact::between(&bodies, a, b, 1.0).is_some() {
+                        brute_force.push((a, b));
+                    }
+                }
+            }
+            let pairs = candidate_pairs(&bodies, 1.0);
+            let mut deduped = pairs.clone();
+            deduped.dedup();
+            assert_eq!(pairs, deduped, "each pair should only come out once");
+            let grid: Vec<(usize, usize)> = find_contacts(&bodies, 1.0)
+                .iter()
+                .map(|contact| (contact.a, contact.b))
+                .collect();
+            assert!(!brute_force.is_empty());
+            assert_eq!(grid, brute_force, "with {count} bodies");
         }
     }
 }
@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+
+use super::Player;
+use crate::prelude::*;
+
+/// Settings for the damage meter. When it's turned on, every punch a player takes adds to their
+/// damage, and the more damage they've taken the further punches knock them.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct Damage {
+    pub enabled: bool,
+}
+
+impl Damage {
+    /// How much farther than usual a punch knocks back a player who has taken `damage` percent
+    pub fn knockback_scale(&self, damage: f32) -> f32 {
+        if self.enabled {
+            1.0 + damage * DAMAGE_KNOCKBACK_SCALE
+        } else {
+            1.0
+        }
+    }
+}
+
+/// A component to mark the text next to a player's points that shows how much damage they've taken
+#[derive(Component, Copy, Clone, Default)]
+pub struct DamageMeter;
+
+/// Keep each player's damage percentage up to date and upright above them, and hide it unless the
+/// damage meter is on
+pub(super) fn damage_meter_system(
+    damage: Res<Damage>,
+    mut meter_query: Query<
+        (&mut Text, &mut Visibility, &mut Transform, &Parent),
+        With<DamageMeter>,
+    >,
+    player_query: Query<(&Player, &Transform), Without<DamageMeter>>,
+) {
+    for (mut text, mut visibility, mut transform, parent) in meter_query.iter_mut() {
+        let Ok((player, player_transform)) = player_query.get(parent.get()) else {
+            continue;
+        };
+        // Undo the player's turning, so the meter doesn't spin around them as they aim
+        let rotation = player_transform.rotation.inverse();
+        transform.rotation = rotation;
+        transform.translation = rotation * Vec3::new(0.0, DAMAGE_METER_OFFSET, LAYER_POINTS);
+        *visibility = if damage.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        let value = format!("{:.0}%", player.damage);
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use bevy::{math::Vec3Swizzles, prelude::*, utils::HashMap};
+
+use super::{Dead, Player, Stunned};
+use crate::{gamepad::GamepadInputs, powerup::PowerUp, prelude::*};
+
+/// A hold one player has on another
+#[derive(Clone, Debug)]
+pub struct Grab {
+    /// Who is being held
+    pub target: PlayerID,
+    /// When this runs out, the target gets thrown
+    pub timer: Timer,
+}
+
+impl Grab {
+    pub fn new(target: PlayerID) -> Self {
+        Self {
+            target,
+            timer: Timer::from_seconds(GRAB_DURATION, TimerMode::Once),
+        }
+    }
+}
+
+/// Start grabs, let held players mash their way out, and throw anyone who's been held long enough
+pub(super) fn grab_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    gamepad_inputs: Res<GamepadInputs>,
+    mut player_query: Query<(Entity, &mut Player, &Transform, Option<&Stunned>), Without<Dead>>,
+    power_up_query: Query<&PowerUp>,
+) {
+    // Let go of anyone who isn't around anymore (or who got away some other way), and stop being
+    // held by anyone who let go
+    let holds: HashMap<PlayerID, PlayerID> = player_query
+        .iter()
+        .filter_map(|(_, player, _, _)| Some((player.grab.as_ref()?.target, player.id)))
+        .collect();
+    let held: HashMap<PlayerID, PlayerID> = player_query
+        .iter()
+        .filter_map(|(_, player, _, _)| Some((player.id, player.grabbed_by?)))
+        .collect();
+    for (_, mut player, _, _) in player_query.iter_mut() {
+        if let Some(target) = player.grab.as_ref().map(|grab| grab.target) {
+            if held.get(&target) != Some(&player.id) {
+                player.grab = None;
+            }
+        }
+        if let Some(grabber) = player.grabbed_by {
+            if holds.get(&player.id) != Some(&grabber) {
+                player.grabbed_by = None;
+            }
+        }
+    }
+
+    // Grab the closest player within reach in front of you
+    let positions: Vec<(PlayerID, Vec2, bool)> = player_query
+        .iter()
+        .map(|(_, player, transform, _)| {
+            let available =
+                player.grab.is_none() && player.grabbed_by.is_none() && !player.dashing();
+            (player.id, transform.translation.xy(), available)
+        })
+        .collect();
+    let mut new_grabs: Vec<(PlayerID, PlayerID)> = Vec::new();
+    // Nobody can take part in more than one new grab, so two players can't grab each other and
+    // grabs can't chain
+    let grabbing = |new_grabs: &[(PlayerID, PlayerID)], id: PlayerID| {
+        new_grabs
+            .iter()
+            .any(|&(grabber, target)| grabber == id || target == id)
+    };
+    for (_, player, transform, stunned) in player_query.iter() {
+        let grab_pressed = gamepad_inputs
+            .inputs
+            .get(&player.id)
+            .is_some_and(|input| input.grab_pressed);
+        if !grab_pressed
+            || player.grab.is_some()
+            || player.grabbed_by.is_some()
+            || stunned.is_some()
+            || player.blocking
+            || player.charge.is_some()
+            || !player.punch_timer.finished()
+            || grabbing(&new_grabs, player.id)
+        {
+            continue;
+        }
+        let center = transform.translation.xy();
+        let target = positions
+            .iter()
+            .filter(|(id, position, available)| {
+                *id != player.id
+                    && *available
+                    && !grabbing(&new_grabs, *id)
+                    && position.distance(center) < GRAB_RANGE
+                    && player.facing.dot(*position - center) > 0.0
+            })
+            .min_by(|a, b| a.1.distance(center).total_cmp(&b.1.distance(center)));
+        if let Some(&(target, _, _)) = target {
+            println!("Player {} grabs player {}!", player.id, target);
+            new_grabs.push((player.id, target));
+        }
+    }
+
+    // Hang on, mash, and throw
+    let mut releases: Vec<(PlayerID, PlayerID, Option<Vec2>)> = Vec::new();
+    for (_, mut player, _, _) in player_query.iter_mut() {
+        let id = player.id;
+        if let Some(&(_, target)) = new_grabs.iter().find(|(grabber, _)| *grabber == id) {
+            player.grab = Some(Grab::new(target));
+        }
+        if let Some(&(grabber, _)) = new_grabs.iter().find(|(_, target)| *target == id) {
+            player.grabbed_by = Some(grabber);
+            player.grab_mashes = 0;
+            player.charge = None;
+        }
+        let facing = player.facing;
+        if let Some(grab) = player.grab.as_mut() {
+            grab.timer.tick(time.delta());
+            if grab.timer.finished() {
+                releases.push((id, grab.target, Some(facing * GRAB_THROW_SPEED)));
+            }
+        }
+        if let Some(grabber) = player.grabbed_by {
+            let mashed = gamepad_inputs
+                .inputs
+                .get(&id)
+                .is_some_and(|input| input.mash);
+            if mashed {
+                player.grab_mashes += 1;
+                if player.grab_mashes >= GRAB_BREAK_MASHES {
+                    println!("Player {} broke free of player {}!", id, grabber);
+                    releases.push((grabber, id, None));
+                }
+            }
+        }
+    }
+    for (entity, mut player, _, _) in player_query.iter_mut() {
+        for &(grabber, target, throw) in &releases {
+            if player.id == grabber {
+                player.grab = None;
+                if throw.is_none() {
+                    // Getting shaken off leaves you off balance for a moment
+                    commands
+                        .entity(entity)
+                        .insert(Stunned::new(GRAB_BREAK_STUN_DURATION));
+                }
+            }
+            if player.id == target {
+                player.grabbed_by = None;
+                if let Some(throw) = throw {
+                    println!("Player {} throws player {}!", grabber, target);
+                    player.vel = throw / player.mass(power_up_query.get(entity).ok());
+                    player.point_timer.reset();
+                    player.point_recipient = Some(grabber);
+                }
+            }
+        }
+    }
+}
+
+/// Keep held players right in front of whoever is holding them, so they swing around as the
+/// grabber turns to aim a throw
+pub(super) fn hold_grabbed_system(
+    mut player_query: Query<(&mut Player, &mut Transform), Without<Dead>>,
+) {
+    let holds: HashMap<PlayerID, (Vec2, Vec2)> = player_query
+        .iter()
+        .filter_map(|(player, transform)| {
+            let grab = player.grab.as_ref()?;
+            let position = transform.translation.xy() + player.facing * COLLISION_RADIUS * 2.0;
+            Some((grab.target, (position, player.vel)))
+        })
+        .collect();
+    for (mut player, mut transform) in player_query.iter_mut() {
+        if player.grabbed_by.is_none() {
+            continue;
+        }
+        if let Some(&(position, vel)) = holds.get(&player.id) {
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+            player.vel = vel;
+        }
+    }
+}
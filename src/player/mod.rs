@@ -1,16 +1,28 @@
 use std::time::Duration;
 
 use crate::{
-    arena::Arena, event::PlayerSpawnEvent, gamepad::GamepadInputs, points::Points, prelude::*,
-};
-use bevy::{
-    math::Vec3Swizzles,
+    arena::{Arena, Hazard, HazardKind, Obstacle, ObstacleKind},
+    event::PlayerSpawnEvent,
+    gamepad::{GamepadInputSet, GamepadInputs},
+    mode::ActiveGameMode,
+    points::Points,
+    powerup::{PowerUp, PowerUpKind},
     prelude::*,
-    utils::{HashMap, HashSet},
+    projectile::Projectile,
+    state::GameState,
 };
+use bevy::{math::Vec3Swizzles, prelude::*, utils::HashMap};
 
-mod collision;
-use collision::Collision;
+pub mod collision;
+mod damage;
+mod grab;
+mod stun;
+mod teeter;
+use collision::{Body, BodyId};
+pub use damage::{Damage, DamageMeter};
+pub use grab::Grab;
+pub use stun::Stunned;
+pub use teeter::{Teeter, Teetering};
 
 /// Plugin for all resources and systems in this module
 #[derive(Default)]
@@ -18,11 +30,46 @@ pub struct PlayerPlugin;
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(PlayerColors::default())
+            .init_resource::<Teeter>()
+            .init_resource::<Damage>()
             .add_system(dead_players_system)
-            .add_system(leave_arena_system)
+            .add_system(
+                leave_arena_system
+                    .in_set(OnUpdate(GameState::Playing))
+                    .after(player_physics_system),
+            )
             .add_system(player_join_system)
-            .add_system(player_physics_system)
-            .add_system(punch_animation_system);
+            .add_system(
+                player_physics_system
+                    .in_set(OnUpdate(GameState::Playing))
+                    .after(GamepadInputSet),
+            )
+            .add_system(
+                teeter::teeter_system
+                    .in_set(OnUpdate(GameState::Playing))
+                    .after(GamepadInputSet)
+                    .before(player_physics_system),
+            )
+            .add_system(
+                stun::stun_system
+                    .in_set(OnUpdate(GameState::Playing))
+                    .before(player_physics_system),
+            )
+            .add_system(
+                grab::grab_system
+                    .in_set(OnUpdate(GameState::Playing))
+                    .after(GamepadInputSet)
+                    .before(player_physics_system),
+            )
+            .add_system(
+                grab::hold_grabbed_system
+                    .in_set(OnUpdate(GameState::Playing))
+                    .after(player_physics_system)
+                    .before(leave_arena_system),
+            )
+            .add_system(punch_animation_system)
+            .add_system(dash_meter_system.after(player_physics_system))
+            .add_system(damage::damage_meter_system.after(player_physics_system));
     }
 }
 
@@ -39,39 +86,164 @@ impl Default for PlayerColors {
         ])
     }
 }
+impl PlayerColors {
+    /// The color for a given player
+    pub fn get(&self, player_id: PlayerID) -> Color {
+        self.0[player_id % self.0.len()]
+    }
+}
 /// A component to use to store most player attributes. Translation, scale, and rotation are in a
 /// separate Transform component that Bevy provides.
 #[derive(Component)]
 pub struct Player {
     /// Player ID
     pub id: PlayerID,
+    /// Which way the player (and their glove) is pointing
     pub facing: Vec2,
     pub vel: Vec2,
+    /// Whether the player has their guard up
+    pub blocking: bool,
+    /// How hard the player is to push around, relative to everyone else, before any power-up
+    pub base_mass: f32,
+    /// How bouncy the player is when bumping into other players, from 0.0 (not at all) to 1.0
+    pub restitution: f32,
     pub respawn_timer: Timer,
     pub punch_timer: Timer,
+    /// How long the punch trigger has been held down to charge up a punch, if it has
+    pub charge: Option<f32>,
+    /// How hard the current punch knocks back whoever it hits
+    pub punch_pushback: f32,
+    /// How far the glove punches outward, before any power-up
+    pub base_punch_length: f32,
+    /// How long it takes to draw the glove back after a punch, before any power-up
+    pub base_punch_drawback: f32,
+    /// How much damage the player has taken since they last respawned, as a percentage. Only
+    /// builds up when the damage meter is turned on.
+    pub damage: f32,
+    /// While this is running, the player is dashing, and can't be hit
+    pub dash_timer: Timer,
+    /// The player can't dash again until this runs out
+    pub dash_cooldown: Timer,
+    /// The player can't throw anything again until this runs out
+    pub throw_cooldown: Timer,
+    /// How far the glove was from the center of the player last frame, to sweep it from there
+    pub last_glove_reach: f32,
+    /// Everyone the current punch has already hit, so a punch only hits each player once
+    pub punch_hits: Vec<PlayerID>,
+    /// Who this player is holding onto, if anyone
+    pub grab: Option<Grab>,
+    /// Who is holding onto this player, if anyone
+    pub grabbed_by: Option<PlayerID>,
+    /// How many times this player has mashed a button trying to break free of a grab
+    pub grab_mashes: u32,
     pub point_recipient: Option<PlayerID>,
     pub point_timer: Timer,
 }
 impl Player {
     pub fn new(id: PlayerID) -> Self {
-        let mut punch_timer = Timer::from_seconds(PUNCH_DRAWBACK_DURATION, TimerMode::Once);
         // For the sake of animation, the timer should be "finished" to start with.
-        punch_timer.tick(Duration::from_secs_f32(PUNCH_DRAWBACK_DURATION * 2.0));
+        let punch_timer = finished_timer(PUNCH_DRAWBACK_DURATION);
         Self {
             id,
             facing: Vec2::X,
             vel: Vec2::ZERO,
+            blocking: false,
+            base_mass: PLAYER_MASS,
+            restitution: PLAYER_RESTITUTION,
             respawn_timer: Timer::from_seconds(RESPAWN_DURATION, TimerMode::Once),
             punch_timer,
+            charge: None,
+            punch_pushback: PUNCH_PUSHBACK_OTHER,
+            base_punch_length: PUNCH_LENGTH,
+            base_punch_drawback: PUNCH_DRAWBACK_DURATION,
+            damage: 0.0,
+            dash_timer: finished_timer(DASH_DURATION),
+            dash_cooldown: finished_timer(DASH_COOLDOWN),
+            throw_cooldown: finished_timer(PROJECTILE_COOLDOWN),
+            last_glove_reach: PUNCH_BASE,
+            punch_hits: Vec::new(),
+            grab: None,
+            grabbed_by: None,
+            grab_mashes: 0,
             point_recipient: None,
             point_timer: Timer::from_seconds(POINT_TOUCH_DURATION, TimerMode::Once),
         }
     }
+
+    /// Whether the player is in the middle of a dash
+    pub fn dashing(&self) -> bool {
+        !self.dash_timer.finished()
+    }
+
+    /// Whether the player is holding someone or being held
+    pub fn grappling(&self) -> bool {
+        self.grab.is_some() || self.grabbed_by.is_some()
+    }
+
+    /// How hard the player is to push around right now, with their power-up (if any) applied
+    pub fn mass(&self, power_up: Option<&PowerUp>) -> f32 {
+        self.base_mass * power_up.map_or(1.0, PowerUp::mass_scale)
+    }
+
+    /// How far the glove punches outward right now, with their power-up (if any) applied
+    pub fn punch_length(&self, power_up: Option<&PowerUp>) -> f32 {
+        self.base_punch_length * power_up.map_or(1.0, PowerUp::punch_length_scale)
+    }
+
+    /// How long it takes to draw the glove back right now, with their power-up (if any) applied
+    pub fn punch_drawback(&self, power_up: Option<&PowerUp>) -> f32 {
+        self.base_punch_drawback * power_up.map_or(1.0, PowerUp::punch_drawback_scale)
+    }
+
+    /// How fast the player can move right now, as a fraction of the usual speed
+    pub fn speed_scale(&self, power_up: Option<&PowerUp>) -> f32 {
+        power_up.map_or(1.0, PowerUp::speed_scale)
+    }
+
+    /// Apply velocity changes from punches (or pushback from punching), which can take the player
+    /// past max velocity. Heavier players get knocked back less.
+    pub fn knock_back(&mut self, vel_deltas: Option<&Vec<Vec2>>, power_up: Option<&PowerUp>) {
+        let mass = self.mass(power_up);
+        for &delta in vel_deltas.into_iter().flatten() {
+            self.vel += delta / mass;
+        }
+    }
+
+    /// Snap the glove straight back, ending the current punch
+    pub fn end_punch(&mut self) {
+        let duration = self.punch_timer.duration();
+        self.punch_timer.set_elapsed(duration);
+    }
+
+    /// How charged up the current punch is, from 0.0 to 1.0
+    pub fn charge_fraction(&self) -> f32 {
+        self.charge
+            .map_or(0.0, |charge| (charge / PUNCH_CHARGE_DURATION).min(1.0))
+    }
+
+    /// How far the glove is from the center of the player right now. It winds back while a punch
+    /// is charging, shoots all the way out when the punch is thrown, and is drawn back as the
+    /// punch timer runs out.
+    pub fn glove_reach(&self, power_up: Option<&PowerUp>) -> f32 {
+        PUNCH_BASE + self.punch_length(power_up) * self.punch_timer.percent_left()
+            - PUNCH_WINDUP * self.charge_fraction()
+    }
+}
+/// A timer that has already run out
+fn finished_timer(seconds: f32) -> Timer {
+    let mut timer = Timer::from_seconds(seconds, TimerMode::Once);
+    timer.tick(Duration::from_secs_f32(seconds * 2.0));
+    timer
 }
+
 /// A component to mark that a player is dead
 #[derive(Component, Copy, Clone, Default)]
 pub struct Dead {}
 
+/// A component to mark the bar under a player that shows how long until they can dash again
+#[derive(Component, Copy, Clone, Default)]
+pub struct DashMeter;
+
 /// A component to mark that something is a boxing glove
 #[derive(Component, Copy, Clone, Default)]
 pub struct Glove {}
@@ -82,10 +254,25 @@ impl Glove {
     }
 }
 
-/// Determine whether something with a position and velocity is moving towards or away from a point
-fn moving_towards(toward_pos: Vec2, obj_pos: Vec2, obj_vel: Vec2) -> bool {
-    let position_vector = toward_pos - obj_pos;
-    position_vector.dot(obj_vel) > 0.0
+/// A glove that's out and can hit someone this frame
+struct Punch {
+    puncher_id: PlayerID,
+    /// Which way the punch is thrown
+    direction: Vec2,
+    /// Where the glove was at the start of the frame
+    glove_start: Vec2,
+    /// Where the glove is at the end of the frame
+    glove_end: Vec2,
+    /// How fast the puncher is moving
+    vel: Vec2,
+    /// How hard the glove hits, from 1.0 when it's fully extended down to 0.0 when it's back
+    strength: f32,
+    /// How hard the punch knocks back whoever it hits at full strength
+    pushback: f32,
+    /// Whether the punch was thrown this frame
+    fresh: bool,
+    /// Everyone this punch has already hit
+    already_hit: Vec<PlayerID>,
 }
 
 /// Animate and respawn dead players
@@ -93,6 +280,7 @@ pub fn dead_players_system(
     mut commands: Commands,
     time: Res<Time>,
     mut players: Query<(Entity, &mut Player, &mut Transform), With<Dead>>,
+    arena_query: Query<(&Arena, &Transform), Without<Player>>,
 ) {
     for (entity, mut player, mut transform) in players.iter_mut() {
         // Decrement the timer for how long the player has left to be dead
@@ -105,45 +293,81 @@ pub fn dead_players_system(
             transform.scale = Vec3::ONE;
             // Restart the timer for next time
             player.respawn_timer.reset();
-            // Reset velocity
+            // Reset velocity and damage
             player.vel = Vec2::ZERO;
+            player.damage = 0.0;
             // Spawn at the starting location
-            transform.translation = STARTING_LOCATIONS[player.id].into();
+            transform.translation = Arena::spawn_location(arena_query.get_single().ok(), player.id);
             // Remove the "Dead" component
             commands.entity(entity).remove::<Dead>();
         }
     }
 }
 
-/// Detect a player leaving the arena, and mark him dead.
+/// Detect a player leaving the arena (or falling into a hole), and mark him dead. If teetering is
+/// turned on, a player who drifts slowly off the edge teeters there instead, and only falls if
+/// they don't recover in time or get punched while they're teetering.
 fn leave_arena_system(
     mut commands: Commands,
-    mut player_query: Query<(Entity, &Transform, &mut Player), Without<Dead>>,
-    arena_transform_query: Query<&Transform, With<Arena>>,
-    mut points_query: Query<(&mut Points, &mut Text)>,
+    mut player_query: Query<(Entity, &Transform, &mut Player, Option<&Teetering>), Without<Dead>>,
+    arena_query: Query<(&Arena, &Transform)>,
+    hazard_query: Query<&Hazard>,
+    game_mode: Res<ActiveGameMode>,
+    teeter: Res<Teeter>,
+    mut points_query: Query<&mut Points>,
 ) {
     let mut points_awarded = Vec::new();
-    for arena_transform in arena_transform_query.iter() {
-        for (entity, player_transform, player) in player_query.iter_mut() {
-            if (player_transform.translation - arena_transform.translation).length() > ARENA_RADIUS
-            {
+    for (arena, arena_transform) in arena_query.iter() {
+        for (entity, player_transform, player, teetering) in player_query.iter_mut() {
+            let local_position =
+                Arena::to_local(arena_transform, player_transform.translation.xy());
+            let in_hole = hazard_query
+                .iter()
+                .any(|hazard| hazard.kind == HazardKind::Hole && hazard.contains(local_position));
+            if !arena.shape.contains(local_position) && !in_hole {
+                match teetering {
+                    // Still hanging on
+                    Some(teetering)
+                        if !teetering.timer.finished()
+                            && player.vel.length() <= TEETER_MAX_VELOCITY =>
+                    {
+                        continue;
+                    }
+                    // Just went over the edge, but not fast enough to fall right away
+                    None if teeter::can_teeter(&teeter, player.vel) => {
+                        println!("Player {} is teetering on the edge!", player.id);
+                        commands.entity(entity).insert(Teetering::default());
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            if !arena.shape.contains(local_position) || in_hole {
                 if let Some(puncher_id) = player.point_recipient {
                     println!(
                         "Player {} was punched out of the arena by player {}.",
                         player.id, puncher_id
                     );
-                    points_awarded.push(puncher_id);
                 } else {
                     println!(
                         "Player {} didn't watch where they were going, and fell off the arena.",
                         player.id
                     );
                 }
-                commands.entity(entity).insert(Dead {});
+                if let Some(recipient) = game_mode
+                    .0
+                    .knockout_points(player.id, player.point_recipient)
+                {
+                    points_awarded.push(recipient);
+                }
+                commands
+                    .entity(entity)
+                    .remove::<Teetering>()
+                    .insert(Dead {});
             }
         }
     }
-    for (mut points, mut text) in points_query.iter_mut() {
+    for mut points in points_query.iter_mut() {
         let new_points = points_awarded
             .iter()
             .filter(|&&x| x == points.player_id)
@@ -156,117 +380,346 @@ fn leave_arena_system(
             "Player {} now has {} points",
             points.player_id, points.value
         );
-        text.sections[0].value = format!("{}", points.value);
     }
 }
 
 /// Game physics - The bulk of the movement / punching logic
+#[allow(clippy::too_many_arguments)]
 pub fn player_physics_system(
+    mut commands: Commands,
     time: Res<Time>,
     gamepad_inputs: Res<GamepadInputs>,
-    mut player_query: Query<(&mut Player, &mut Transform), Without<Dead>>,
+    damage: Res<Damage>,
+    mut player_query: Query<
+        (Entity, &mut Player, &mut Transform, Option<&Teetering>),
+        Without<Dead>,
+    >,
+    stunned_query: Query<&Stunned>,
+    power_up_query: Query<&PowerUp>,
+    mut projectile_query: Query<(Entity, &mut Projectile)>,
+    arena_query: Query<(&Arena, &Transform), Without<Player>>,
+    obstacle_query: Query<&Obstacle>,
+    hazard_query: Query<&Hazard>,
 ) {
-    // Iterate through each player and collect positions so we can do collision detection
-    let mut player_positions: HashMap<PlayerID, Vec2> = HashMap::new();
-    let mut player_velocities: HashMap<PlayerID, Vec2> = HashMap::new();
-    for (player, transform) in player_query.iter_mut() {
-        player_positions.insert(player.id, transform.translation.xy());
-        player_velocities.insert(player.id, player.vel);
-    }
-    // For each player, store positions of other players that we are colliding with (because we could hit more than one at once)
-    let mut player_collisions: HashSet<Collision> = HashSet::new();
-    for (&player_id1, &pos1) in player_positions.iter() {
-        for (&player_id2, &pos2) in player_positions.iter() {
-            // Don't collide with one's self
-            if player_id1 == player_id2 {
-                continue;
-            }
-            if (pos1 - pos2).length() < COLLISION_RADIUS * 2.0 {
-                let collision = Collision {
-                    player_id1,
-                    player_id2,
-                    pos1,
-                    pos2,
-                    vel1: player_velocities[&player_id1],
-                    vel2: player_velocities[&player_id2],
-                };
-                player_collisions.insert(collision);
-            }
-        }
-    }
-
-    // For each player, store the direction and location of each punch
-    let mut punches: Vec<(PlayerID, Quat, Vec2)> = Vec::new();
-    for (mut player, transform) in player_query.iter_mut() {
-        if !gamepad_inputs
-            .inputs
-            .get(&player.id)
-            .unwrap()
-            .right_trigger2
+    // How far something moving at a velocity of 1.0 travels this frame
+    let step = time.delta_seconds() * MOVE_SPEED;
+    // Start new punches, and store where every glove that's out is sweeping through this frame
+    let mut punches: Vec<Punch> = Vec::new();
+    for (entity, mut player, transform, _) in player_query.iter_mut() {
+        let stunned = stunned_query.get(entity).ok();
+        let power_up = power_up_query.get(entity).ok();
+        let input = gamepad_inputs.inputs.get(&player.id).unwrap();
+        // Holding block puts your guard up, as long as you aren't in the middle of something else
+        player.blocking = input.block
+            && player.punch_timer.finished()
+            && player.charge.is_none()
+            && stunned.is_none()
+            && !player.dashing()
+            && !player.grappling();
+        // Pressing the trigger starts charging up a punch. Can't punch until previous punch has
+        // finished, or while stunned, blocking, or grappling.
+        if input.right_trigger2_pressed
+            && player.punch_timer.finished()
+            && stunned.is_none()
+            && !player.blocking
+            && !player.grappling()
         {
-            continue;
+            player.charge = Some(0.0);
         }
-        // Can't punch until previous punch has finished
-        if !player.punch_timer.finished() {
+        if stunned.is_some() {
+            player.charge = None;
+        }
+        // Letting go of the trigger throws the punch, harder the longer it was charged
+        let mut fresh = false;
+        if let Some(charge) = player.charge {
+            if input.right_trigger2_released || !input.right_trigger2 {
+                //println!("Player {} punches", player.id);
+                player.punch_pushback = PUNCH_PUSHBACK_OTHER
+                    + (PUNCH_PUSHBACK_CHARGED - PUNCH_PUSHBACK_OTHER) * player.charge_fraction();
+                player.charge = None;
+                player.punch_timer =
+                    Timer::from_seconds(player.punch_drawback(power_up), TimerMode::Once);
+                player.punch_hits.clear();
+                fresh = true;
+            } else {
+                player.charge = Some(charge + time.delta_seconds());
+            }
+        }
+        let reach = player.glove_reach(power_up);
+        let last_reach = std::mem::replace(&mut player.last_glove_reach, reach);
+        if player.punch_timer.finished() {
             continue;
         }
-        //println!("Player {} punches", player.id);
-        player.punch_timer.reset();
-        punches.push((
-            player.id,
-            transform.rotation,
-            (transform.translation + transform.rotation * (Vec3::X * (PUNCH_BASE + PUNCH_LENGTH)))
-                .xy(),
-        ));
+        let direction = (transform.rotation * Vec3::X).xy();
+        let center = transform.translation.xy();
+        punches.push(Punch {
+            puncher_id: player.id,
+            direction,
+            glove_start: center + direction * last_reach,
+            glove_end: center + direction * reach,
+            vel: player.vel,
+            strength: player.punch_timer.percent_left(),
+            pushback: player.punch_pushback,
+            fresh,
+            already_hit: player.punch_hits.clone(),
+        });
     }
     // For each punch, store velocity deltas for who got punched and who got pushed back from
     // punching someone else, to be resolved during the physics step.
     let mut punch_vel_deltas: HashMap<PlayerID, Vec<Vec2>> = HashMap::new();
-    for (mut punchee, transform) in player_query.iter_mut() {
-        for (puncher_id, direction, punch) in &punches {
-            // Players are unable to punch themselves
-            if *puncher_id == punchee.id {
+
+    // Gloves that run into each other clash instead of hitting anybody. Store how long each player
+    // involved is stunned for.
+    let mut clashes: HashMap<PlayerID, f32> = HashMap::new();
+    for (i, a) in punches.iter().enumerate() {
+        for b in &punches[i + 1..] {
+            let a_motion = a.glove_end - a.glove_start + a.vel * step;
+            let b_motion = b.glove_end - b.glove_start + b.vel * step;
+            if collision::time_of_impact(
+                b.glove_start - a.glove_start,
+                b_motion - a_motion,
+                GLOVE_RADIUS * 2.0,
+            )
+            .is_none()
+            {
                 continue;
             }
-            let punch_vector = transform.translation.xy() - *punch;
-            // Did the punch connect?
-            if punch_vector.length() < 2.0 * COLLISION_RADIUS {
-                // Handle point timer on punchee
-                punchee.point_timer.reset();
-                punchee.point_recipient = Some(*puncher_id);
-                // Process punch physics
-                let punch_delta =
-                    ((*direction * Vec3::X) * (PUNCH_PUSHBACK_OTHER * MAX_VELOCITY)).xy();
-                punch_vel_deltas
-                    .entry(punchee.id)
-                    .or_default()
-                    .push(punch_delta);
-                let pushback_delta =
-                    ((*direction * Vec3::X) * (-1.0 * PUNCH_PUSHBACK_SELF * MAX_VELOCITY)).xy();
+            // Punching right into a glove that's coming at you is a parry: the attacker is left
+            // wide open, and the parrying player is free to follow up
+            let parry = match (a.fresh, b.fresh) {
+                (true, false) if b.strength >= PARRY_MIN_STRENGTH => Some((a, b)),
+                (false, true) if a.strength >= PARRY_MIN_STRENGTH => Some((b, a)),
+                _ => None,
+            };
+            if let Some((parrier, attacker)) = parry {
+                println!(
+                    "Player {} parried player {}'s punch!",
+                    parrier.puncher_id, attacker.puncher_id
+                );
+                clashes.insert(parrier.puncher_id, 0.0);
+                clashes.insert(attacker.puncher_id, PARRY_STUN_DURATION);
                 punch_vel_deltas
-                    .entry(*puncher_id)
+                    .entry(attacker.puncher_id)
                     .or_default()
-                    .push(pushback_delta);
+                    .push(-attacker.direction * CLASH_PUSHBACK * MAX_VELOCITY);
+            } else {
+                println!(
+                    "Players {} and {} clash gloves!",
+                    a.puncher_id, b.puncher_id
+                );
+                for punch in [a, b] {
+                    clashes.insert(punch.puncher_id, CLASH_STUN_DURATION);
+                    punch_vel_deltas
+                        .entry(punch.puncher_id)
+                        .or_default()
+                        .push(-punch.direction * CLASH_PUSHBACK * MAX_VELOCITY);
+                }
             }
         }
     }
+    punches.retain(|punch| !clashes.contains_key(&punch.puncher_id));
+
+    let mut punch_hits: Vec<(PlayerID, PlayerID)> = Vec::new();
+    // Players who got punched out of a grab (either side of it)
+    let mut grabs_broken: Vec<PlayerID> = Vec::new();
+    for (entity, mut punchee, transform, _) in player_query.iter_mut() {
+        for punch in &punches {
+            // Players are unable to punch themselves, or hit someone twice with the same punch, or
+            // hit someone who is dashing. A glove that's nearly drawn back doesn't hit anyone, so
+            // brushing past someone on the way back can't steal a knockout.
+            if punch.puncher_id == punchee.id
+                || punch.already_hit.contains(&punchee.id)
+                || punchee.dashing()
+                || punch.strength < PUNCH_MIN_STRENGTH
+            {
+                continue;
+            }
+            let punch_vector = transform.translation.xy() - punch.glove_start;
+            // Did the glove connect anywhere along its path this frame? Both players' motion is
+            // swept too, so someone flying past at high speed can't slip through the glove.
+            let relative_motion =
+                (punchee.vel - punch.vel) * step - (punch.glove_end - punch.glove_start);
+            if collision::time_of_impact(
+                punch_vector,
+                relative_motion,
+                COLLISION_RADIUS + GLOVE_RADIUS,
+            )
+            .is_none()
+            {
+                continue;
+            }
+            punch_hits.push((punch.puncher_id, punchee.id));
+            let shielded = power_up_query
+                .get(entity)
+                .is_ok_and(|power_up| power_up.kind == PowerUpKind::Shield);
+            if shielded {
+                println!(
+                    "Player {}'s shield absorbs player {}'s punch!",
+                    punchee.id, punch.puncher_id
+                );
+                commands.entity(entity).remove::<PowerUp>();
+                continue;
+            }
+            if punchee.grappling() {
+                grabs_broken.push(punchee.id);
+            }
+            // Handle point timer on punchee
+            punchee.point_timer.reset();
+            punchee.point_recipient = Some(punch.puncher_id);
+            // A punch from the front into someone's guard barely moves them, but their guard
+            // doesn't help against a punch from behind
+            let toward_glove = (punch.glove_start - transform.translation.xy()).normalize_or_zero();
+            let blocked = punchee.blocking && punchee.facing.dot(toward_glove) >= BLOCK_ARC_COS;
+            let block_scale = if blocked {
+                //println!("Player {} blocks player {}", punchee.id, punch.puncher_id);
+                BLOCK_PUSHBACK_SCALE
+            } else {
+                1.0
+            };
+            // Process punch physics (a glove that's mostly drawn back doesn't hit as hard, and
+            // players who have taken a lot of damage fly farther)
+            let punch_delta = punch.direction
+                * punch.pushback
+                * MAX_VELOCITY
+                * punch.strength
+                * block_scale
+                * damage.knockback_scale(punchee.damage);
+            if damage.enabled {
+                punchee.damage +=
+                    DAMAGE_PER_PUSHBACK * punch.pushback * punch.strength * block_scale;
+            }
+            punch_vel_deltas
+                .entry(punchee.id)
+                .or_default()
+                .push(punch_delta);
+            // Getting hit leaves you reeling for a moment, longer the harder the hit. Landing
+            // another punch before that wears off keeps the combo going.
+            let stun = Stunned::from_hit(
+                stunned_query.get(entity).ok(),
+                HIT_STUN_PER_PUSHBACK * punch.pushback * punch.strength * block_scale,
+            );
+            if stun.combo > 1 {
+                println!(
+                    "Player {} lands a {} hit combo on player {}!",
+                    punch.puncher_id, stun.combo, punchee.id
+                );
+            }
+            commands.entity(entity).insert(stun);
+            let pushback_delta =
+                -punch.direction * PUNCH_PUSHBACK_SELF * MAX_VELOCITY * punch.strength;
+            punch_vel_deltas
+                .entry(punch.puncher_id)
+                .or_default()
+                .push(pushback_delta);
+        }
+    }
+    for (entity, mut player, _, _) in player_query.iter_mut() {
+        if grabs_broken.contains(&player.id) {
+            // The other player lets go (or stops being held) next frame, when the grab notices
+            player.grab = None;
+            player.grabbed_by = None;
+        }
+        if let Some(&stun) = clashes.get(&player.id) {
+            player.end_punch();
+            if stun > 0.0 {
+                commands.entity(entity).insert(Stunned::new(stun));
+            }
+        }
+        let id = player.id;
+        player.punch_hits.extend(
+            punch_hits
+                .iter()
+                .filter(|(puncher_id, _)| *puncher_id == id)
+                .map(|(_, punchee_id)| punchee_id),
+        );
+    }
+
+    // Iterate through each player and work out how they want to move
+    let mut conveyor_velocities: HashMap<PlayerID, Vec2> = HashMap::new();
+    for (entity, mut player, transform, teetering) in player_query.iter_mut() {
+        let stunned = stunned_query.get(entity).ok();
+        let power_up = power_up_query.get(entity).ok();
+        let input = gamepad_inputs.inputs.get(&player.id).unwrap();
+
+        // Dash the way we're moving (or the way we're facing, if we're standing still)
+        player.dash_timer.tick(time.delta());
+        player.dash_cooldown.tick(time.delta());
+        if input.dash_pressed
+            && player.dash_cooldown.finished()
+            && stunned.is_none()
+            && !player.grappling()
+            && teetering.is_none()
+        {
+            let direction = if input.left_stick.length() > DEAD_ZONE_THRESHOLD {
+                input.left_stick.normalize()
+            } else {
+                (transform.rotation * Vec3::X).xy()
+            };
+            player.vel = direction * DASH_SPEED;
+            player.dash_timer.reset();
+            player.dash_cooldown.reset();
+        }
+        if player.dashing() {
+            // Nothing slows down or steers a dash, but recoil from the dasher's own punches (or a
+            // clash) still pushes them around
+            let vel_deltas = punch_vel_deltas.get(&player.id);
+            player.knock_back(vel_deltas, power_up);
+            conveyor_velocities.insert(player.id, Vec2::ZERO);
+            continue;
+        }
+        if player.dash_timer.just_finished() {
+            // Stop short at the end of a dash, instead of coasting down from dash speed
+            player.vel = player.vel.clamp_length_max(MAX_VELOCITY);
+        }
 
-    // Iterate through each player and apply physics
-    for (mut player, mut transform) in player_query.iter_mut() {
         // Collect some info so we can deal with different slowing mechanics if you've been punched
         let starting_velocity = player.vel.length();
-        let coming_down_to_max = starting_velocity > MAX_VELOCITY;
+        let speed_scale = player.speed_scale(power_up);
+        let coming_down_to_max = starting_velocity > MAX_VELOCITY * speed_scale;
+
+        // See what kind of floor we're standing on
+        let mut drag = DRAG;
+        let mut conveyor_velocity = Vec2::ZERO;
+        if let Ok((_, arena_transform)) = arena_query.get_single() {
+            let local_position = Arena::to_local(arena_transform, transform.translation.xy());
+            for hazard in hazard_query.iter() {
+                if !hazard.contains(local_position) {
+                    continue;
+                }
+                match hazard.kind {
+                    HazardKind::Ice { drag: ice_drag } => drag *= ice_drag,
+                    HazardKind::Conveyor { velocity } => {
+                        conveyor_velocity += (arena_transform.rotation * velocity.extend(0.0)).xy()
+                            * arena_transform.scale.x
+                    }
+                    HazardKind::Hole => {}
+                }
+            }
+        }
 
         // Apply fixed drag so players slow to a stop eventually
-        player.vel *= 1.0 - time.delta_seconds() * DRAG;
+        player.vel *= 1.0 - time.delta_seconds() * drag;
 
-        // Adjust velocity based on gamepad input
-        let input = gamepad_inputs.inputs.get(&player.id).unwrap();
+        // Adjust velocity based on gamepad input (unless we're too busy teetering on the edge,
+        // reeling from a stun, or being held). Players move slower while they charge up a punch or
+        // block.
         let left_x = input.left_stick.x;
         let left_y = input.left_stick.y;
-        if Vec2::new(left_x, left_y).length() > DEAD_ZONE_THRESHOLD {
-            player.vel.x += left_x * time.delta_seconds() * MOVE_SPEED;
-            player.vel.y += left_y * time.delta_seconds() * MOVE_SPEED;
+        let move_scale = speed_scale
+            * if player.charge.is_some() {
+                PUNCH_CHARGE_MOVE_SCALE
+            } else if player.blocking {
+                BLOCK_MOVE_SCALE
+            } else {
+                1.0
+            };
+        if teetering.is_none()
+            && stunned.is_none()
+            && player.grabbed_by.is_none()
+            && Vec2::new(left_x, left_y).length() > DEAD_ZONE_THRESHOLD
+        {
+            player.vel.x += left_x * time.delta_seconds() * MOVE_SPEED * move_scale;
+            player.vel.y += left_y * time.delta_seconds() * MOVE_SPEED * move_scale;
         }
         // Make sure velocity doesn't go too high
         if coming_down_to_max {
@@ -277,43 +730,142 @@ pub fn player_physics_system(
                     * starting_velocity
                     * (1.0 - time.delta_seconds() * DRAG * 2.0);
             }
-        } else if player.vel.length() > MAX_VELOCITY {
+        } else if player.vel.length() > MAX_VELOCITY * move_scale {
             // We're moving normally, so cap velocity
-            player.vel = player.vel.normalize() * MAX_VELOCITY;
+            player.vel = player.vel.normalize() * MAX_VELOCITY * move_scale;
         }
 
         // Process any punches (or pushbacks from punches) that affect velocity - these can exceed max velocity
-        if let Some(vel_deltas) = punch_vel_deltas.get(&player.id) {
-            for &delta in vel_deltas {
-                player.vel += delta;
+        let vel_deltas = punch_vel_deltas.get(&player.id);
+        player.knock_back(vel_deltas, power_up);
+        conveyor_velocities.insert(player.id, conveyor_velocity);
+    }
+
+    // Projectiles just coast along, slowing down a little
+    for (_, mut projectile) in projectile_query.iter_mut() {
+        projectile.vel *= 1.0 - time.delta_seconds() * PROJECTILE_DRAG;
+    }
+
+    // Resolve collisions between players and projectiles all at once, so pileups of several
+    // players work out, and projectiles hand their momentum over to whoever they hit
+    let mut bodies: Vec<Body> = player_query
+        .iter()
+        .map(|(entity, player, transform, _)| {
+            let mass = player.mass(power_up_query.get(entity).ok());
+            Body {
+                id: BodyId::Player(player.id),
+                pos: transform.translation.xy(),
+                vel: player.vel,
+                radius: COLLISION_RADIUS,
+                inv_mass: if mass > 0.0 { 1.0 / mass } else { 0.0 },
+                restitution: player.restitution,
             }
+        })
+        .collect();
+    bodies.extend(
+        projectile_query
+            .iter()
+            .map(|(entity, projectile)| projectile.body(entity)),
+    );
+    // Players holding each other don't bounce off of each other
+    let grabs: Vec<(BodyId, BodyId)> = player_query
+        .iter()
+        .filter_map(|(_, player, _, _)| {
+            let target = player.grab.as_ref()?.target;
+            Some((BodyId::Player(player.id), BodyId::Player(target)))
+        })
+        .collect();
+    let mut contacts = collision::find_contacts(&bodies, step);
+    contacts.retain(|contact| {
+        let pair = (bodies[contact.a].id, bodies[contact.b].id);
+        !grabs.contains(&pair) && !grabs.contains(&(pair.1, pair.0))
+    });
+    collision::resolve_contacts(&mut bodies, &mut contacts);
+    // Which projectiles ran into which players
+    let projectile_hits: Vec<(Entity, PlayerID)> = contacts
+        .iter()
+        .filter_map(
+            |contact| match (bodies[contact.a].id, bodies[contact.b].id) {
+                (BodyId::Projectile(entity), BodyId::Player(player_id))
+                | (BodyId::Player(player_id), BodyId::Projectile(entity)) => {
+                    Some((entity, player_id))
+                }
+                _ => None,
+            },
+        )
+        .collect();
+    let bodies: HashMap<BodyId, Body> = bodies.into_iter().map(|body| (body.id, body)).collect();
+
+    // Iterate through each projectile and apply physics
+    let arena_transform = arena_query
+        .get_single()
+        .ok()
+        .map(|(_, transform)| transform);
+    let mut projectile_throwers: HashMap<Entity, PlayerID> = HashMap::new();
+    for (entity, mut projectile) in projectile_query.iter_mut() {
+        let body = bodies[&BodyId::Projectile(entity)];
+        let mut pos = (body.pos + body.vel * step).extend(0.0);
+        let mut vel = body.vel;
+        if let Some(arena_transform) = arena_transform {
+            bounce_off_obstacles(
+                &mut pos,
+                &mut vel,
+                projectile.kind.radius(),
+                arena_transform,
+                &obstacle_query,
+            );
         }
+        projectile.pos = pos.xy();
+        projectile.vel = vel;
+        let hit_someone = projectile_hits.iter().any(|&(hit_entity, player_id)| {
+            hit_entity == entity && player_id != projectile.thrower
+        });
+        if hit_someone && projectile.kind.breaks_on_hit() {
+            // Used up, so it goes away as soon as it's done hitting
+            let duration = projectile.lifetime.duration();
+            projectile.lifetime.set_elapsed(duration);
+        }
+        projectile_throwers.insert(entity, projectile.thrower);
+    }
 
-        // Process this player's collisions, adjusting velocities accordingly
-        for collision in player_collisions.iter() {
-            if let Some(new_velocity) = collision.new_velocity(player.id) {
-                // Don't collide if we aren't moving toward each other (let them depenetrate)
-                let relative_vel = collision.vel2 - collision.vel1;
-                if !moving_towards(collision.pos1, collision.pos2, relative_vel) {
-                    // Already still or moving away, but overlapping. Let's give the player a nudge.
-                    player.vel.x *= 1.0 + MOVE_SPEED * time.delta_seconds();
-                    player.vel.y *= 1.0 + MOVE_SPEED * time.delta_seconds();
-                    // ...but don't shoot across the screen like a bullet. Clamp to max velocity.
-                    if player.vel.length() > MAX_VELOCITY {
-                        player.vel = player.vel.normalize() * MAX_VELOCITY;
-                    }
-                    continue;
-                }
-                // Accept the new velocity calculated by the collision
-                player.vel = new_velocity;
-            }
+    // Iterate through each player and apply physics
+    for (_, mut player, mut transform, _) in player_query.iter_mut() {
+        // Getting hit by someone else's projectile counts as them touching you
+        let thrower = projectile_hits
+            .iter()
+            .filter(|(_, player_id)| *player_id == player.id)
+            .filter_map(|(entity, _)| projectile_throwers.get(entity).copied())
+            .find(|&thrower| thrower != player.id);
+        if let Some(thrower) = thrower {
+            player.point_timer.reset();
+            player.point_recipient = Some(thrower);
         }
 
+        let body = bodies[&BodyId::Player(player.id)];
+        player.vel = body.vel;
+        transform.translation.x = body.pos.x;
+        transform.translation.y = body.pos.y;
+
         // Apply velocity to position
-        transform.translation.x += player.vel.x * time.delta_seconds() * MOVE_SPEED;
-        transform.translation.y += player.vel.y * time.delta_seconds() * MOVE_SPEED;
+        transform.translation.x += player.vel.x * step;
+        transform.translation.y += player.vel.y * step;
+        // Conveyors carry you along without changing your own velocity
+        transform.translation +=
+            (conveyor_velocities[&player.id] * time.delta_seconds()).extend(0.0);
+
+        // Bounce off of any obstacles we've run into
+        if let Some(arena_transform) = arena_transform {
+            bounce_off_obstacles(
+                &mut transform.translation,
+                &mut player.vel,
+                COLLISION_RADIUS,
+                arena_transform,
+                &obstacle_query,
+            );
+        }
 
         // Set direction of player with right stick
+        let input = gamepad_inputs.inputs.get(&player.id).unwrap();
         let facing_vec = Vec2::new(input.right_stick.x, input.right_stick.y);
         if facing_vec.length() > DEAD_ZONE_THRESHOLD {
             let quat =
@@ -328,22 +880,85 @@ pub fn player_physics_system(
                     (transform.rotation * -1.0).slerp(quat, TURN_SPEED * time.delta_seconds());
             }
         }
+        player.facing = (transform.rotation * Vec3::X).xy();
+    }
+}
+
+/// Push a circle of `radius` back out of any obstacles it has run into, and bounce its velocity
+/// off of them
+fn bounce_off_obstacles(
+    translation: &mut Vec3,
+    vel: &mut Vec2,
+    radius: f32,
+    arena_transform: &Transform,
+    obstacle_query: &Query<&Obstacle>,
+) {
+    // Obstacles are in the arena's local space, so that's where we test for contact
+    let scale = arena_transform.scale.x;
+    let local_position = Arena::to_local(arena_transform, translation.xy());
+    for obstacle in obstacle_query.iter() {
+        let Some((local_normal, depth)) = obstacle.penetration(local_position, radius / scale)
+        else {
+            continue;
+        };
+        let normal = (arena_transform.rotation * local_normal.extend(0.0)).xy();
+        // Push back out of the obstacle
+        *translation += (normal * depth * scale).extend(0.0);
+        // Reflect the part of the velocity that was heading into the obstacle
+        let into_obstacle = vel.dot(normal);
+        if into_obstacle < 0.0 {
+            *vel -= normal * into_obstacle * (1.0 + OBSTACLE_RESTITUTION);
+        }
+        if let ObstacleKind::Bumper { boost } = obstacle.kind {
+            *vel += normal * boost;
+        }
     }
 }
 
-/// Animate punches
+/// Show how long until each player can dash again, upright under them, and hide the meter when
+/// they can
+pub fn dash_meter_system(
+    mut meter_query: Query<(&mut Transform, &mut Visibility, &Parent), With<DashMeter>>,
+    player_query: Query<(&Player, &Transform), Without<DashMeter>>,
+) {
+    for (mut transform, mut visibility, parent) in meter_query.iter_mut() {
+        let Ok((player, player_transform)) = player_query.get(parent.get()) else {
+            continue;
+        };
+        // Undo the player's turning, so the meter doesn't circle around them as they aim
+        let rotation = player_transform.rotation.inverse();
+        transform.rotation = rotation;
+        transform.translation = rotation * Vec3::new(0.0, -DASH_METER_OFFSET, LAYER_POINTS);
+        let cooldown = player.dash_cooldown.percent_left();
+        transform.scale.x = cooldown;
+        *visibility = if player.dash_cooldown.finished() {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+}
+
+/// Animate punches, and tint the glove while its player is blocking or powered up
 pub fn punch_animation_system(
     time: Res<Time>,
-    mut glove_query: Query<(&mut Transform, &Parent), With<Glove>>,
+    mut glove_query: Query<(&mut Transform, &mut Sprite, &Parent), With<Glove>>,
     mut player_query: Query<&mut Player>,
+    power_up_query: Query<&PowerUp>,
 ) {
-    for (mut transform, parent) in glove_query.iter_mut() {
+    for (mut transform, mut sprite, parent) in glove_query.iter_mut() {
         let mut player = player_query.get_mut(parent.get()).unwrap();
+        let power_up = power_up_query.get(parent.get()).ok();
         player.punch_timer.tick(time.delta());
-        let punch_base_vec3 = Vec3::from(PUNCH_BASE_ARR3);
-        let punch_extended_vec3 = Vec3::from(PUNCH_EXTENDED_ARR3);
-        transform.translation =
-            punch_base_vec3.lerp(punch_extended_vec3, player.punch_timer.percent_left());
+        // Put the glove wherever the punch, drawback or charge has it
+        transform.translation = Vec3::new(player.glove_reach(power_up), 0.0, LAYER_GLOVE);
+        sprite.color = if player.blocking {
+            Color::rgb(0.5, 0.65, 1.0)
+        } else if let Some(power_up) = power_up {
+            power_up.kind.color()
+        } else {
+            Color::WHITE
+        };
     }
 }
 
@@ -353,6 +968,7 @@ pub fn player_join_system(
     colors: Res<PlayerColors>,
     mut player_spawn_events: EventReader<PlayerSpawnEvent>,
     asset_server: Res<AssetServer>,
+    arena_query: Query<(&Arena, &Transform)>,
 ) {
     for player_spawn_event in player_spawn_events.iter() {
         let player_id: PlayerID = player_spawn_event.id;
@@ -360,7 +976,10 @@ pub fn player_join_system(
         commands
             .spawn(SpriteBundle {
                 texture: asset_server.load("circle.png"),
-                transform: Transform::from_translation(STARTING_LOCATIONS[player_id].into()),
+                transform: Transform::from_translation(Arena::spawn_location(
+                    arena_query.get_single().ok(),
+                    player_id,
+                )),
                 sprite: Sprite {
                     color: colors.0[player_id],
                     ..default()
@@ -392,6 +1011,42 @@ pub fn player_join_system(
                         ..default()
                     })
                     .insert(Points::new(player_id));
+                parent
+                    .spawn(Text2dBundle {
+                        text: Text::from_section(
+                            "0%",
+                            TextStyle {
+                                font: asset_server.load("FiraMono-Medium.ttf"),
+                                font_size: 24.0,
+                                color: Color::WHITE,
+                            },
+                        )
+                        .with_alignment(TextAlignment::Center),
+                        transform: Transform::from_translation(Vec3::new(
+                            0.0,
+                            DAMAGE_METER_OFFSET,
+                            LAYER_POINTS,
+                        )),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    })
+                    .insert(DamageMeter);
+                parent
+                    .spawn(SpriteBundle {
+                        sprite: Sprite {
+                            color: Color::rgba(1.0, 1.0, 1.0, 0.8),
+                            custom_size: Some(Vec2::new(COLLISION_RADIUS * 1.5, 6.0)),
+                            ..default()
+                        },
+                        transform: Transform::from_translation(Vec3::new(
+                            0.0,
+                            -DASH_METER_OFFSET,
+                            LAYER_POINTS,
+                        )),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    })
+                    .insert(DashMeter);
             });
     }
 }
@@ -0,0 +1,61 @@
+use bevy::prelude::*;
+
+use super::{Player, PlayerColors};
+use crate::prelude::*;
+
+/// How many times per second a stunned player's sprite flashes
+const STUN_FLASH_SPEED: f32 = 8.0;
+
+/// A component to mark that a player is reeling, from getting punched, clashing gloves, or being
+/// shaken off in a grab. Stunned players can't steer or punch.
+#[derive(Component, Clone, Debug)]
+pub struct Stunned {
+    pub timer: Timer,
+    /// How many punches in a row have landed on the player without giving them a chance to recover
+    pub combo: u32,
+}
+
+impl Stunned {
+    pub fn new(seconds: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+            combo: 0,
+        }
+    }
+
+    /// The stun from a punch landing for `seconds` worth of hit-stun. A punch that lands while the
+    /// player is still stunned from the last one continues the combo, though each hit in a combo
+    /// stuns for less time than the one before so combos can't go on forever.
+    pub fn from_hit(current: Option<&Stunned>, seconds: f32) -> Self {
+        let combo = current.map_or(1, |stunned| stunned.combo + 1);
+        let seconds = seconds * HIT_STUN_COMBO_SCALE.powi(combo as i32 - 1);
+        let remaining = current.map_or(0.0, |stunned| stunned.timer.remaining_secs());
+        Self {
+            timer: Timer::from_seconds(seconds.max(remaining), TimerMode::Once),
+            combo,
+        }
+    }
+}
+
+/// Count down stuns, and flash stunned players so everyone can see they're helpless
+pub(super) fn stun_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    colors: Res<PlayerColors>,
+    mut player_query: Query<(Entity, &Player, &mut Stunned, &mut Sprite)>,
+) {
+    for (entity, player, mut stunned, mut sprite) in player_query.iter_mut() {
+        stunned.timer.tick(time.delta());
+        if stunned.timer.finished() {
+            sprite.color = colors.get(player.id);
+            commands.entity(entity).remove::<Stunned>();
+            continue;
+        }
+        let flash = (stunned.timer.elapsed_secs() * STUN_FLASH_SPEED).fract() < 0.5;
+        sprite.color = if flash {
+            Color::WHITE
+        } else {
+            colors.get(player.id)
+        };
+    }
+}
@@ -0,0 +1,84 @@
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+use super::{Dead, Player};
+use crate::{arena::Arena, gamepad::GamepadInputs, prelude::*};
+
+/// How much a teetering player's sprite grows and shrinks as they wobble
+const WOBBLE_AMOUNT: f32 = 0.12;
+/// How many times per second a teetering player wobbles
+const WOBBLE_SPEED: f32 = 4.0;
+
+/// Settings for teetering on the edge of the arena
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct Teeter {
+    pub enabled: bool,
+}
+
+/// A component to mark that a player is teetering on the edge of the arena. Push back toward the
+/// arena before the timer runs out to recover, or get punched while teetering to fall.
+#[derive(Component, Clone, Debug)]
+pub struct Teetering {
+    pub timer: Timer,
+}
+
+impl Default for Teetering {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(TEETER_DURATION, TimerMode::Once),
+        }
+    }
+}
+
+/// Whether a player drifting off the edge at this velocity gets to teeter instead of falling
+pub fn can_teeter(teeter: &Teeter, velocity: Vec2) -> bool {
+    teeter.enabled && velocity.length() <= TEETER_MAX_VELOCITY
+}
+
+/// Wobble teetering players, and pull them back onto the arena if they push back toward it
+pub(super) fn teeter_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    gamepad_inputs: Res<GamepadInputs>,
+    mut player_query: Query<(Entity, &mut Player, &mut Transform, &mut Teetering), Without<Dead>>,
+    arena_query: Query<(&Arena, &Transform), Without<Player>>,
+) {
+    let Ok((arena, arena_transform)) = arena_query.get_single() else {
+        return;
+    };
+    let scale = arena_transform.scale.x;
+    for (entity, mut player, mut transform, mut teetering) in player_query.iter_mut() {
+        teetering.timer.tick(time.delta());
+        let local_position = Arena::to_local(arena_transform, transform.translation.xy());
+        // The arena moved back underneath them (or they were sent back to their spawn point)
+        if arena.shape.contains(local_position) {
+            transform.scale = Vec3::ONE;
+            commands.entity(entity).remove::<Teetering>();
+            continue;
+        }
+        // Teetering players can't walk around, they can only try to get their balance back
+        player.vel = Vec2::ZERO;
+        let inward =
+            -(arena_transform.rotation * arena.shape.normal(local_position).extend(0.0)).xy();
+        let stick = gamepad_inputs
+            .inputs
+            .get(&player.id)
+            .map_or(Vec2::ZERO, |input| input.left_stick);
+        if stick.length() > DEAD_ZONE_THRESHOLD
+            && stick.normalize().dot(inward) > TEETER_RECOVERY_THRESHOLD
+        {
+            println!("Player {} caught their balance.", player.id);
+            // Step back over the edge, far enough to be standing on the floor again
+            let overhang = arena.shape.distance(local_position) * scale;
+            transform.translation += (inward * (overhang + COLLISION_RADIUS * 0.5)).extend(0.0);
+            transform.scale = Vec3::ONE;
+            commands.entity(entity).remove::<Teetering>();
+            continue;
+        }
+        let wobble = (teetering.timer.elapsed_secs() * WOBBLE_SPEED * std::f32::consts::TAU).sin();
+        transform.scale = Vec3::new(
+            1.0 + wobble * WOBBLE_AMOUNT,
+            1.0 - wobble * WOBBLE_AMOUNT,
+            1.0,
+        );
+    }
+}
@@ -1,13 +1,25 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 
-use crate::{player::Player, prelude::*};
+use crate::{
+    event::{MatchOverEvent, RoundResetEvent},
+    mode::ActiveGameMode,
+    player::Player,
+    prelude::*,
+    state::GameState,
+};
 
 #[derive(Default)]
 pub struct PointsPlugin;
 impl Plugin for PointsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(point_decay_system)
-            .add_system(win_system)
+        app.init_resource::<ActiveGameMode>()
+            .init_resource::<MatchScore>()
+            .add_startup_system(announce_game_mode_system)
+            .add_startup_system(spawn_scoreboard_system)
+            .add_system(point_decay_system.in_set(OnUpdate(GameState::Playing)))
+            .add_system(points_hud_system)
+            .add_system(scoreboard_system)
+            .add_system(win_system.in_set(OnUpdate(GameState::Playing)))
             .add_system(cleanup_win_message_system);
     }
 }
@@ -27,9 +39,64 @@ impl Points {
     }
 }
 
+/// Round wins over the course of a match. A round is won according to the `GameMode`, and the first
+/// player to win `rounds_to_win` rounds wins the match.
+#[derive(Resource, Clone, Debug)]
+pub struct MatchScore {
+    pub rounds_to_win: usize,
+    /// Which round we're on, starting at 1
+    pub round: usize,
+    pub round_wins: HashMap<PlayerID, usize>,
+}
+
+impl Default for MatchScore {
+    fn default() -> Self {
+        Self::new(ROUNDS_TO_WIN)
+    }
+}
+
+impl MatchScore {
+    pub fn new(rounds_to_win: usize) -> Self {
+        Self {
+            rounds_to_win: rounds_to_win.max(1),
+            round: 1,
+            round_wins: HashMap::default(),
+        }
+    }
+
+    /// A match that is the best of `rounds` rounds (rounded up to an odd number)
+    pub fn best_of(rounds: usize) -> Self {
+        Self::new(rounds / 2 + 1)
+    }
+
+    /// How many rounds a player has won so far this match
+    pub fn wins(&self, player_id: PlayerID) -> usize {
+        self.round_wins.get(&player_id).copied().unwrap_or(0)
+    }
+
+    /// Record a round win, and return whether it won the match
+    pub fn record_round_win(&mut self, player_id: PlayerID) -> bool {
+        let wins = self.round_wins.entry(player_id).or_default();
+        *wins += 1;
+        *wins >= self.rounds_to_win
+    }
+
+    /// Start a fresh match
+    pub fn reset(&mut self) {
+        self.round = 1;
+        self.round_wins.clear();
+    }
+}
+
+/// Marker for the text at the top of the screen that shows round wins
+#[derive(Component, Copy, Clone, Default)]
+pub struct Scoreboard;
+
 #[derive(Component, Clone, Default)]
 pub struct WinningMessage {
     timer: Timer,
+    /// Whether this win ended the match, rather than just the round
+    match_over: bool,
 }
 
 pub fn point_decay_system(time: Res<Time>, mut players: Query<&mut Player>) {
@@ -40,29 +107,93 @@ pub fn point_decay_system(time: Res<Time>, mut players: Query<&mut Player>) {
     }
 }
 
+fn announce_game_mode_system(game_mode: Res<ActiveGameMode>) {
+    println!("Game mode: {}", game_mode.0.name());
+}
+
+fn spawn_scoreboard_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("FiraMono-Medium.ttf"),
+                    font_size: 36.0,
+                    color: Color::WHITE,
+                },
+            )
+            .with_alignment(TextAlignment::Center),
+            transform: Transform::from_translation(Vec3::new(0.0, 480.0, LAYER_POINTS)),
+            ..default()
+        })
+        .insert(Scoreboard);
+}
+
+/// Show the round number and everyone's round wins at the top of the screen
+fn scoreboard_system(
+    match_score: Res<MatchScore>,
+    player_query: Query<&Player>,
+    mut scoreboard_query: Query<&mut Text, With<Scoreboard>>,
+) {
+    let mut player_ids: Vec<PlayerID> = player_query.iter().map(|player| player.id).collect();
+    player_ids.sort_unstable();
+    let mut value = format!(
+        "Round {} (first to {})",
+        match_score.round, match_score.rounds_to_win
+    );
+    for player_id in player_ids {
+        value.push_str(&format!(
+            "   P{}: {}",
+            player_id,
+            match_score.wins(player_id)
+        ));
+    }
+    for mut text in scoreboard_query.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+/// Keep the text showing each player's points up to date
+pub fn points_hud_system(
+    game_mode: Res<ActiveGameMode>,
+    mut points_query: Query<(&Points, &mut Text), Changed<Points>>,
+) {
+    for (points, mut text) in points_query.iter_mut() {
+        text.sections[0].value = game_mode.0.hud_text(points);
+    }
+}
+
 pub fn win_system(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    game_mode: Res<ActiveGameMode>,
+    mut match_score: ResMut<MatchScore>,
     points_query: Query<&Points>,
     winning_message_query: Query<&WinningMessage>,
+    mut match_over_events: EventWriter<MatchOverEvent>,
 ) {
     if winning_message_query.iter().next().is_some() {
         // Someone has already won, so don't trigger another win until the message has disappeared
         return;
     }
-    let mut winning_player = None;
-    for points in points_query.iter() {
-        if points.value >= WIN_POINTS {
-            winning_player = Some(points.player_id);
-            break;
-        }
-    }
-    if let Some(player_id) = winning_player {
+    let points: Vec<Points> = points_query.iter().copied().collect();
+    if let Some(player_id) = game_mode.0.round_winner(&points) {
+        let match_over = match_score.record_round_win(player_id);
+        let message = if match_over {
+            println!("Player {} wins the match!", player_id);
+            match_over_events.send(MatchOverEvent { winner: player_id });
+            game_mode.0.match_win_message(player_id)
+        } else {
+            println!("Player {} wins round {}", player_id, match_score.round);
+            game_mode.0.win_message(player_id)
+        };
         // Create the winning message
         commands
             .spawn(Text2dBundle {
                 text: Text::from_section(
-                    format!("Player {} wins!", player_id),
+                    message,
                     TextStyle {
                         font: asset_server.load("FiraMono-Medium.ttf"),
                         font_size: 90.0,
@@ -75,6 +206,7 @@ pub fn win_system(
             })
             .insert(WinningMessage {
                 timer: Timer::from_seconds(3.0, TimerMode::Once),
+                match_over,
             });
     }
 }
@@ -82,22 +214,65 @@ pub fn win_system(
 fn cleanup_win_message_system(
     mut commands: Commands,
     time: Res<Time>,
+    mut match_score: ResMut<MatchScore>,
     mut winning_message_query: Query<(Entity, &mut WinningMessage)>,
-    mut points_query: Query<(&mut Points, &mut Text)>,
-    mut player_query: Query<(&mut Player, &mut Transform)>,
+    mut points_query: Query<&mut Points>,
+    mut round_reset_events: EventWriter<RoundResetEvent>,
 ) {
     for (entity, mut winning_message) in winning_message_query.iter_mut() {
         if winning_message.timer.tick(time.delta()).finished() {
             commands.entity(entity).despawn();
-            // Reset the game
-            for (mut points, mut text) in points_query.iter_mut() {
-                points.value = 0;
-                text.sections[0].value = "0".to_string();
+            if winning_message.match_over {
+                match_score.reset();
+            } else {
+                match_score.round += 1;
             }
-            for (mut player, mut transform) in player_query.iter_mut() {
-                transform.translation = STARTING_LOCATIONS[player.id].into();
-                player.vel = Vec2::ZERO;
+            // Reset for the next round (players are sent back to their spawn points by the arena)
+            for mut points in points_query.iter_mut() {
+                points.value = 0;
             }
+            round_reset_events.send(RoundResetEvent);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn best_of() {
+        assert_eq!(MatchScore::best_of(1).rounds_to_win, 1);
+        assert_eq!(MatchScore::best_of(3).rounds_to_win, 2);
+        assert_eq!(MatchScore::best_of(5).rounds_to_win, 3);
+        // Even numbers are rounded up, so there's never a tie
+        assert_eq!(MatchScore::best_of(4).rounds_to_win, 3);
+        // There's always at least one round
+        assert_eq!(MatchScore::best_of(0).rounds_to_win, 1);
+        assert_eq!(MatchScore::new(0).rounds_to_win, 1);
+    }
+
+    #[test]
+    fn record_round_win() {
+        let mut score = MatchScore::best_of(3);
+        assert!(!score.record_round_win(0));
+        assert!(!score.record_round_win(1));
+        assert_eq!(score.wins(0), 1);
+        assert_eq!(score.wins(1), 1);
+        assert_eq!(score.wins(2), 0);
+        assert!(score.record_round_win(1));
+        assert_eq!(score.wins(1), 2);
+    }
+
+    #[test]
+    fn reset() {
+        let mut score = MatchScore::best_of(3);
+        score.round = 3;
+        score.record_round_win(0);
+        score.reset();
+        assert_eq!(score.round, 1);
+        assert_eq!(score.wins(0), 0);
+        assert_eq!(score.rounds_to_win, 2);
+        assert!(!score.record_round_win(0));
+    }
+}
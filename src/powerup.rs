@@ -0,0 +1,276 @@
+use bevy::{math::Vec3Swizzles, prelude::*};
+use rand::seq::SliceRandom;
+
+use crate::{
+    arena::{Arena, Hazard, Obstacle},
+    event::RoundResetEvent,
+    mode::ActiveGameMode,
+    player::{player_physics_system, Dead, Player},
+    prelude::*,
+    state::GameState,
+};
+
+#[derive(Default)]
+pub struct PowerUpPlugin;
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PowerUps>()
+            .add_system(power_up_spawn_system.in_set(OnUpdate(GameState::Playing)))
+            .add_system(
+                pickup_system
+                    .in_set(OnUpdate(GameState::Playing))
+                    .after(power_up_spawn_system),
+            )
+            .add_system(
+                power_up_system
+                    .in_set(OnUpdate(GameState::Playing))
+                    .after(pickup_system)
+                    .before(player_physics_system),
+            )
+            .add_system(power_up_reset_system);
+    }
+}
+
+/// The different things a pickup can do for whoever grabs it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerUpKind {
+    /// Punches reach farther
+    GiantGlove,
+    /// Move faster
+    Speed,
+    /// Get knocked around less, by punches and by other players
+    Heavy,
+    /// Draw the glove back faster, to punch again sooner
+    RapidPunch,
+    /// Shrug off the next punch that lands
+    Shield,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 5] = [
+        PowerUpKind::GiantGlove,
+        PowerUpKind::Speed,
+        PowerUpKind::Heavy,
+        PowerUpKind::RapidPunch,
+        PowerUpKind::Shield,
+    ];
+
+    /// Name of the power-up, for messages
+    pub fn name(&self) -> &str {
+        match self {
+            PowerUpKind::GiantGlove => "a giant glove",
+            PowerUpKind::Speed => "a speed boost",
+            PowerUpKind::Heavy => "heavy",
+            PowerUpKind::RapidPunch => "rapid punch",
+            PowerUpKind::Shield => "a shield",
+        }
+    }
+
+    /// What color the pickup is, and what color the glove turns while the power-up lasts
+    pub fn color(&self) -> Color {
+        match self {
+            PowerUpKind::GiantGlove => Color::rgb(1.0, 0.5, 0.1),
+            PowerUpKind::Speed => Color::rgb(1.0, 0.9, 0.2),
+            PowerUpKind::Heavy => Color::rgb(0.5, 0.5, 0.55),
+            PowerUpKind::RapidPunch => Color::rgb(0.9, 0.2, 0.9),
+            PowerUpKind::Shield => Color::rgb(0.2, 0.9, 0.9),
+        }
+    }
+}
+
+/// Settings for power-ups. When they're turned on, a pickup shows up somewhere on the arena every
+/// so often, chosen from the game mode's spawn table.
+#[derive(Resource, Clone, Debug)]
+pub struct PowerUps {
+    pub enabled: bool,
+    /// When this goes off, another pickup spawns
+    pub spawn_timer: Timer,
+}
+
+impl Default for PowerUps {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            spawn_timer: Timer::from_seconds(POWER_UP_SPAWN_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+impl PowerUps {
+    /// Power-ups with the default spawn timer, turned on
+    pub fn enabled() -> Self {
+        Self {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// A component to mark a power-up lying on the arena floor, waiting for someone to run over it
+#[derive(Component, Copy, Clone, Debug)]
+pub struct Pickup {
+    pub kind: PowerUpKind,
+}
+
+/// A component for a power-up a player has picked up. Its effect is applied over the player's
+/// usual stats until the timer runs out. Picking up another power-up replaces this one.
+#[derive(Component, Clone, Debug)]
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+    pub timer: Timer,
+}
+
+impl PowerUp {
+    pub fn new(kind: PowerUpKind) -> Self {
+        Self {
+            kind,
+            timer: Timer::from_seconds(POWER_UP_DURATION, TimerMode::Once),
+        }
+    }
+
+    /// How much the power-up multiplies how far the glove punches outward
+    pub fn punch_length_scale(&self) -> f32 {
+        match self.kind {
+            PowerUpKind::GiantGlove => GIANT_GLOVE_SCALE,
+            _ => 1.0,
+        }
+    }
+
+    /// How much the power-up multiplies how long it takes to draw the glove back after a punch
+    pub fn punch_drawback_scale(&self) -> f32 {
+        match self.kind {
+            PowerUpKind::RapidPunch => RAPID_PUNCH_SCALE,
+            _ => 1.0,
+        }
+    }
+
+    /// How much the power-up multiplies how fast the player can move
+    pub fn speed_scale(&self) -> f32 {
+        match self.kind {
+            PowerUpKind::Speed => SPEED_BOOST_SCALE,
+            _ => 1.0,
+        }
+    }
+
+    /// How much the power-up multiplies the player's mass
+    pub fn mass_scale(&self) -> f32 {
+        match self.kind {
+            PowerUpKind::Heavy => HEAVY_MASS_SCALE,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Drop a random pickup from the game mode's spawn table somewhere on the arena every so often
+#[allow(clippy::too_many_arguments)]
+fn power_up_spawn_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    game_mode: Res<ActiveGameMode>,
+    mut power_ups: ResMut<PowerUps>,
+    pickup_query: Query<&Pickup>,
+    arena_query: Query<(&Arena, &Transform)>,
+    obstacle_query: Query<&Obstacle>,
+    hazard_query: Query<&Hazard>,
+) {
+    if !power_ups.enabled || !power_ups.spawn_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    if pickup_query.iter().count() >= POWER_UP_MAX_PICKUPS {
+        return;
+    }
+    let Ok((arena, arena_transform)) = arena_query.get_single() else {
+        return;
+    };
+    let table = game_mode.0.power_up_table();
+    let Ok(&(kind, _)) = table.choose_weighted(&mut rand::thread_rng(), |(_, weight)| *weight)
+    else {
+        return;
+    };
+    let margin = POWER_UP_RADIUS / arena_transform.scale.x;
+    let obstacles: Vec<&Obstacle> = obstacle_query.iter().collect();
+    let hazards: Vec<&Hazard> = hazard_query.iter().collect();
+    // Nobody could reach a pickup inside an obstacle or over a hole, so try again next time
+    let Some(position) = arena.random_clear_point(arena_transform, margin, &obstacles, &hazards)
+    else {
+        return;
+    };
+    println!("A power-up appears: {}", kind.name());
+    commands
+        .spawn(SpriteBundle {
+            texture: asset_server.load("circle.png"),
+            transform: Transform::from_translation(position.extend(LAYER_POWER_UP))
+                .with_scale(Vec3::splat(POWER_UP_RADIUS / COLLISION_RADIUS)),
+            sprite: Sprite {
+                color: kind.color(),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(Pickup { kind });
+}
+
+/// Hand out power-ups to players who touch them, and clear away any that end up off the floor
+fn pickup_system(
+    mut commands: Commands,
+    pickup_query: Query<(Entity, &Pickup, &Transform)>,
+    player_query: Query<(Entity, &Player, &Transform), Without<Dead>>,
+    arena_query: Query<(&Arena, &Transform), Without<Pickup>>,
+) {
+    for (pickup_entity, pickup, pickup_transform) in pickup_query.iter() {
+        let position = pickup_transform.translation.xy();
+        if let Ok((arena, arena_transform)) = arena_query.get_single() {
+            if !arena.contains(arena_transform, position) {
+                commands.entity(pickup_entity).despawn();
+                continue;
+            }
+        }
+        let Some((player_entity, player, _)) = player_query.iter().find(|(_, _, transform)| {
+            transform.translation.xy().distance(position) < COLLISION_RADIUS + POWER_UP_RADIUS
+        }) else {
+            continue;
+        };
+        println!("Player {} picks up {}!", player.id, pickup.kind.name());
+        commands
+            .entity(player_entity)
+            .insert(PowerUp::new(pickup.kind));
+        commands.entity(pickup_entity).despawn();
+    }
+}
+
+/// Count down power-ups, and take them away once they wear off. Their effects are applied over
+/// the player's base stats wherever the stats get used, and shields are handled when a punch
+/// lands.
+fn power_up_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut player_query: Query<(Entity, &Player, &mut PowerUp)>,
+) {
+    for (entity, player, mut power_up) in player_query.iter_mut() {
+        if power_up.timer.tick(time.delta()).finished() {
+            println!("Player {}'s {} wears off", player.id, power_up.kind.name());
+            commands.entity(entity).remove::<PowerUp>();
+        }
+    }
+}
+
+/// Clear away pickups and take away everyone's power-ups when a new round starts
+fn power_up_reset_system(
+    mut commands: Commands,
+    mut round_reset_events: EventReader<RoundResetEvent>,
+    mut power_ups: ResMut<PowerUps>,
+    pickup_query: Query<Entity, With<Pickup>>,
+    player_query: Query<Entity, With<PowerUp>>,
+) {
+    if round_reset_events.iter().next().is_none() {
+        return;
+    }
+    power_ups.spawn_timer.reset();
+    for entity in pickup_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    for entity in player_query.iter() {
+        commands.entity(entity).remove::<PowerUp>();
+    }
+}
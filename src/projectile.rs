@@ -0,0 +1,232 @@
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+use crate::{
+    arena::Arena,
+    event::RoundResetEvent,
+    gamepad::{GamepadInputSet, GamepadInputs},
+    player::{
+        collision::{Body, BodyId},
+        player_physics_system, Dead, Player, PlayerColors, Stunned,
+    },
+    prelude::*,
+    state::GameState,
+};
+
+#[derive(Default)]
+pub struct ProjectilePlugin;
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Projectiles>()
+            .add_system(
+                throw_system
+                    .in_set(OnUpdate(GameState::Playing))
+                    .after(GamepadInputSet)
+                    .before(player_physics_system),
+            )
+            .add_system(
+                projectile_system
+                    .in_set(OnUpdate(GameState::Playing))
+                    .after(player_physics_system),
+            )
+            .add_system(projectile_reset_system);
+    }
+}
+
+/// Settings for projectiles
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct Projectiles {
+    pub enabled: bool,
+}
+
+/// The different things players can throw
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProjectileKind {
+    /// A light, bouncy ball that rolls around the arena for a while
+    Ball,
+    /// A spare glove fired straight ahead, which hits hard but is used up on whoever it hits
+    Glove,
+}
+
+impl ProjectileKind {
+    pub fn radius(&self) -> f32 {
+        match self {
+            ProjectileKind::Ball => BALL_RADIUS,
+            ProjectileKind::Glove => GLOVE_RADIUS,
+        }
+    }
+
+    /// How heavy the projectile is, which decides how much of its momentum it hands over to
+    /// whoever it runs into
+    pub fn mass(&self) -> f32 {
+        match self {
+            ProjectileKind::Ball => BALL_MASS,
+            ProjectileKind::Glove => GLOVE_SHOT_MASS,
+        }
+    }
+
+    /// How bouncy the projectile is, from 0.0 to 1.0
+    pub fn restitution(&self) -> f32 {
+        match self {
+            ProjectileKind::Ball => BALL_RESTITUTION,
+            ProjectileKind::Glove => GLOVE_SHOT_RESTITUTION,
+        }
+    }
+
+    /// How fast the projectile leaves the thrower
+    pub fn speed(&self) -> f32 {
+        match self {
+            ProjectileKind::Ball => BALL_THROW_SPEED,
+            ProjectileKind::Glove => GLOVE_SHOT_SPEED,
+        }
+    }
+
+    /// How long the projectile sticks around before disappearing
+    pub fn lifetime(&self) -> f32 {
+        match self {
+            ProjectileKind::Ball => BALL_LIFETIME,
+            ProjectileKind::Glove => GLOVE_SHOT_LIFETIME,
+        }
+    }
+
+    /// Whether the projectile is used up once it hits somebody
+    pub fn breaks_on_hit(&self) -> bool {
+        matches!(self, ProjectileKind::Glove)
+    }
+}
+
+/// Something a player has thrown, flying around the arena. Projectiles are moved and collided in
+/// the physics step along with the players.
+#[derive(Component, Clone, Debug)]
+pub struct Projectile {
+    pub kind: ProjectileKind,
+    /// Who threw it, to credit them if it knocks someone out
+    pub thrower: PlayerID,
+    pub pos: Vec2,
+    pub vel: Vec2,
+    /// When this runs out, the projectile disappears
+    pub lifetime: Timer,
+}
+
+impl Projectile {
+    pub fn new(kind: ProjectileKind, thrower: PlayerID, pos: Vec2, vel: Vec2) -> Self {
+        Self {
+            kind,
+            thrower,
+            pos,
+            vel,
+            lifetime: Timer::from_seconds(kind.lifetime(), TimerMode::Once),
+        }
+    }
+
+    /// The projectile as far as the collision solver is concerned
+    pub fn body(&self, entity: Entity) -> Body {
+        Body {
+            id: BodyId::Projectile(entity),
+            pos: self.pos,
+            vel: self.vel,
+            radius: self.kind.radius(),
+            inv_mass: 1.0 / self.kind.mass(),
+            restitution: self.kind.restitution(),
+        }
+    }
+}
+
+/// Throw a ball or fire a glove the way the player is facing, if they aren't busy with anything
+/// else
+fn throw_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    colors: Res<PlayerColors>,
+    projectiles: Res<Projectiles>,
+    gamepad_inputs: Res<GamepadInputs>,
+    mut player_query: Query<(&mut Player, &Transform, Option<&Stunned>), Without<Dead>>,
+) {
+    for (mut player, transform, stunned) in player_query.iter_mut() {
+        player.throw_cooldown.tick(time.delta());
+        if !projectiles.enabled {
+            continue;
+        }
+        let Some(input) = gamepad_inputs.inputs.get(&player.id) else {
+            continue;
+        };
+        let kind = if input.throw_pressed {
+            ProjectileKind::Ball
+        } else if input.fire_glove_pressed {
+            ProjectileKind::Glove
+        } else {
+            continue;
+        };
+        if !player.throw_cooldown.finished()
+            || stunned.is_some()
+            || player.blocking
+            || player.charge.is_some()
+            || player.dashing()
+            || player.grappling()
+        {
+            continue;
+        }
+        player.throw_cooldown.reset();
+        // Start the projectile just clear of the thrower, so it doesn't bump into them on the
+        // way out
+        let direction = player.facing;
+        let position = transform.translation.xy()
+            + direction * (COLLISION_RADIUS + kind.radius() + CONTACT_SLOP * 2.0);
+        let vel = player.vel + direction * kind.speed();
+        let mut projectile_transform =
+            Transform::from_translation(position.extend(LAYER_PROJECTILE))
+                .with_rotation(Quat::from_rotation_z(direction.y.atan2(direction.x)));
+        let (texture, color) = match kind {
+            ProjectileKind::Ball => {
+                projectile_transform.scale = Vec3::splat(kind.radius() / COLLISION_RADIUS);
+                (asset_server.load("circle.png"), colors.get(player.id))
+            }
+            ProjectileKind::Glove => (asset_server.load("glove.png"), Color::WHITE),
+        };
+        println!("Player {} throws a {:?}", player.id, kind);
+        commands
+            .spawn(SpriteBundle {
+                texture,
+                transform: projectile_transform,
+                sprite: Sprite { color, ..default() },
+                ..default()
+            })
+            .insert(Projectile::new(kind, player.id, position, vel));
+    }
+}
+
+/// Move projectile sprites to wherever the physics step left them, and get rid of projectiles once
+/// they've been around long enough or have fallen off the arena
+fn projectile_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut projectile_query: Query<(Entity, &mut Projectile, &mut Transform)>,
+    arena_query: Query<(&Arena, &Transform), Without<Projectile>>,
+) {
+    for (entity, mut projectile, mut transform) in projectile_query.iter_mut() {
+        transform.translation.x = projectile.pos.x;
+        transform.translation.y = projectile.pos.y;
+        let off_the_floor = arena_query
+            .get_single()
+            .is_ok_and(|(arena, arena_transform)| {
+                !arena.contains(arena_transform, transform.translation.xy())
+            });
+        if projectile.lifetime.tick(time.delta()).finished() || off_the_floor {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Clear away all the projectiles when a new round starts
+fn projectile_reset_system(
+    mut commands: Commands,
+    mut round_reset_events: EventReader<RoundResetEvent>,
+    projectile_query: Query<Entity, With<Projectile>>,
+) {
+    if round_reset_events.iter().next().is_none() {
+        return;
+    }
+    for entity in projectile_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,20 @@
+use bevy::prelude::*;
+
+#[derive(Default)]
+pub struct StatePlugin;
+impl Plugin for StatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<GameState>();
+    }
+}
+
+/// What the game is doing at the moment. Gameplay systems only run while `Playing`.
+#[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum GameState {
+    #[default]
+    Playing,
+    /// Picking which arena to play on before the match
+    ArenaSelect,
+    /// Showing the tournament bracket between matches
+    Bracket,
+}
@@ -0,0 +1,563 @@
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    event::{MatchOverEvent, RoundResetEvent},
+    prelude::*,
+    state::GameState,
+};
+
+/// Plugin for running a local tournament. Only does anything if a `Tournament` resource has been
+/// inserted. Each pairing in the bracket is played as a match between players 0 and 1.
+#[derive(Default)]
+pub struct TournamentPlugin;
+impl Plugin for TournamentPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(tournament_start_system)
+            .add_system(tournament_start_system.in_schedule(OnExit(GameState::ArenaSelect)))
+            .add_system(tournament_result_system)
+            .add_system(tournament_round_reset_system)
+            .add_system(spawn_bracket_screen_system.in_schedule(OnEnter(GameState::Bracket)))
+            .add_system(bracket_screen_system.in_set(OnUpdate(GameState::Bracket)))
+            .add_system(despawn_bracket_screen_system.in_schedule(OnExit(GameState::Bracket)));
+    }
+}
+
+/// How many losses it takes to be knocked out of the tournament
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Elimination {
+    Single,
+    Double,
+}
+
+impl Elimination {
+    pub fn max_losses(self) -> usize {
+        match self {
+            Elimination::Single => 1,
+            Elimination::Double => 2,
+        }
+    }
+}
+
+/// Two entrants (indexes into the roster) who play a match against each other
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Pairing {
+    pub entrants: [usize; 2],
+    pub winner: Option<usize>,
+}
+
+/// One round of the bracket. Entrants who don't have an opponent this round get a bye.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct BracketRound {
+    pub pairings: Vec<Pairing>,
+    pub byes: Vec<usize>,
+}
+
+/// A single- or double-elimination bracket. Each round, entrants are paired up with others who
+/// have the same number of losses (so in double elimination the winners and losers brackets are
+/// played side by side), until only one entrant is left. If the losers bracket champion beats the
+/// winners bracket champion in the final, they both have one loss and play again.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Bracket {
+    pub elimination: Elimination,
+    pub roster: Vec<String>,
+    /// How many matches each entrant has lost, indexed the same as `roster`
+    pub losses: Vec<usize>,
+    pub rounds: Vec<BracketRound>,
+}
+
+impl Bracket {
+    /// Create a bracket with the roster in a random seeding order
+    pub fn new(mut roster: Vec<String>, elimination: Elimination) -> Self {
+        roster.shuffle(&mut rand::thread_rng());
+        let mut bracket = Self {
+            elimination,
+            losses: vec![0; roster.len()],
+            roster,
+            rounds: Vec::new(),
+        };
+        bracket.advance();
+        bracket
+    }
+
+    /// Load a bracket saved by `save`. A save file that doesn't hold together (like one edited by
+    /// hand) is ignored.
+    pub fn load(path: impl AsRef<Path>) -> Option<Self> {
+        let contents = fs::read_to_string(path).ok()?;
+        let bracket: Self = ron::from_str(&contents).ok()?;
+        if !bracket.is_consistent() {
+            println!("The saved tournament doesn't make sense, so it can't be resumed");
+            return None;
+        }
+        Some(bracket)
+    }
+
+    /// Whether every entrant the bracket mentions is on the roster, and every pairing's winner is
+    /// one of the two entrants
+    fn is_consistent(&self) -> bool {
+        let on_roster = |entrant: usize| entrant < self.roster.len();
+        self.roster.len() >= 2
+            && self.losses.len() == self.roster.len()
+            && self.rounds.iter().all(|round| {
+                round.byes.iter().all(|&bye| on_roster(bye))
+                    && round.pairings.iter().all(|pairing| {
+                        let [first, second] = pairing.entrants;
+                        on_roster(first)
+                            && on_roster(second)
+                            && first != second
+                            && pairing
+                                .winner
+                                .into_iter()
+                                .all(|winner| winner == first || winner == second)
+                    })
+            })
+    }
+
+    /// Save the bracket, so the tournament survives a restart
+    pub fn save(&self, path: impl AsRef<Path>) {
+        match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => {
+                if let Err(error) = fs::write(path, contents) {
+                    println!("Unable to save the tournament: {}", error);
+                }
+            }
+            Err(error) => println!("Unable to save the tournament: {}", error),
+        }
+    }
+
+    /// Entrants who haven't been eliminated yet
+    fn active(&self) -> Vec<usize> {
+        (0..self.roster.len())
+            .filter(|&entrant| self.losses[entrant] < self.elimination.max_losses())
+            .collect()
+    }
+
+    /// The winner of the tournament, once there is one
+    pub fn champion(&self) -> Option<usize> {
+        match self.active()[..] {
+            [entrant] => Some(entrant),
+            _ => None,
+        }
+    }
+
+    /// The next pairing that needs to be played, if any
+    pub fn next_pairing(&self) -> Option<Pairing> {
+        self.rounds
+            .last()?
+            .pairings
+            .iter()
+            .find(|pairing| pairing.winner.is_none())
+            .copied()
+    }
+
+    /// Record the result of the next pairing. `slot` is 0 or 1, for the first or second entrant
+    /// of the pairing (who play as player 0 and player 1).
+    pub fn record_winner(&mut self, slot: usize) {
+        let Some(round) = self.rounds.last_mut() else {
+            return;
+        };
+        let Some(pairing) = round
+            .pairings
+            .iter_mut()
+            .find(|pairing| pairing.winner.is_none())
+        else {
+            return;
+        };
+        let winner = pairing.entrants[slot];
+        let loser = pairing.entrants[1 - slot];
+        pairing.winner = Some(winner);
+        self.losses[loser] += 1;
+        if round
+            .pairings
+            .iter()
+            .all(|pairing| pairing.winner.is_some())
+        {
+            self.advance();
+        }
+    }
+
+    /// Pair up everyone still in the tournament for the next round
+    fn advance(&mut self) {
+        let active = self.active();
+        if active.len() < 2 {
+            return;
+        }
+        let mut round = BracketRound::default();
+        if active.len() == 2 {
+            // The final, no matter how many losses each side has
+            round.pairings.push(Pairing {
+                entrants: [active[0], active[1]],
+                winner: None,
+            });
+        } else {
+            for losses in 0..self.elimination.max_losses() {
+                let group: Vec<usize> = active
+                    .iter()
+                    .copied()
+                    .filter(|&entrant| self.losses[entrant] == losses)
+                    .collect();
+                for chunk in group.chunks(2) {
+                    match *chunk {
+                        [first, second] => round.pairings.push(Pairing {
+                            entrants: [first, second],
+                            winner: None,
+                        }),
+                        [bye] => round.byes.push(bye),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        self.rounds.push(round);
+    }
+
+    /// The whole bracket as text, for showing between matches
+    pub fn describe(&self) -> String {
+        let mut lines = Vec::new();
+        for (number, round) in self.rounds.iter().enumerate() {
+            lines.push(format!("Round {}", number + 1));
+            for pairing in round.pairings.iter() {
+                let [first, second] = pairing.entrants;
+                let result = match pairing.winner {
+                    Some(winner) => format!(" -> {}", self.roster[winner]),
+                    None => String::new(),
+                };
+                lines.push(format!(
+                    "  {} vs {}{}",
+                    self.roster[first], self.roster[second], result
+                ));
+            }
+            for &bye in round.byes.iter() {
+                lines.push(format!("  {} (bye)", self.roster[bye]));
+            }
+        }
+        lines.push(String::new());
+        if let Some(champion) = self.champion() {
+            lines.push(format!("{} wins the tournament!", self.roster[champion]));
+        } else if let Some(pairing) = self.next_pairing() {
+            let [first, second] = pairing.entrants;
+            lines.push(format!(
+                "Next: {} (player 0) vs {} (player 1)",
+                self.roster[first], self.roster[second]
+            ));
+            lines.push("Press A to start".to_string());
+        }
+        lines.join("\n")
+    }
+}
+
+/// The tournament in progress
+#[derive(Resource, Clone, Debug)]
+pub struct Tournament {
+    pub bracket: Bracket,
+    /// Set when a match has finished, so the bracket is shown once the game resets
+    show_bracket: bool,
+}
+
+impl Tournament {
+    /// Start a new tournament, or resume the saved one if `roster` is empty. Every entrant needs a
+    /// name, and a finished tournament can't be resumed.
+    pub fn new(roster: Vec<String>, elimination: Elimination) -> Option<Self> {
+        let bracket = if roster.is_empty() {
+            let bracket = Bracket::load(TOURNAMENT_FILE)?;
+            if bracket.champion().is_some() {
+                println!("The saved tournament is already over");
+                return None;
+            }
+            bracket
+        } else if roster.len() < 2 || roster.iter().any(|name| name.is_empty()) {
+            return None;
+        } else {
+            let bracket = Bracket::new(roster, elimination);
+            bracket.save(TOURNAMENT_FILE);
+            bracket
+        };
+        Some(Self {
+            bracket,
+            show_bracket: false,
+        })
+    }
+}
+
+/// Marker for everything on the bracket screen
+#[derive(Component, Clone, Default)]
+pub struct BracketScreen {
+    timer: Timer,
+}
+
+/// Show the bracket before the first match (once an arena has been picked, if there's a picker)
+fn tournament_start_system(
+    tournament: Option<Res<Tournament>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if tournament.is_some() && state.0 != GameState::ArenaSelect && next_state.0.is_none() {
+        next_state.set(GameState::Bracket);
+    }
+}
+
+/// Advance the winner of each match through the bracket
+fn tournament_result_system(
+    tournament: Option<ResMut<Tournament>>,
+    mut match_over_events: EventReader<MatchOverEvent>,
+) {
+    let Some(mut tournament) = tournament else {
+        return;
+    };
+    for event in match_over_events.iter() {
+        if tournament.bracket.champion().is_some() {
+            continue;
+        }
+        // Only players 0 and 1 are playing for the bracket
+        if event.winner > 1 {
+            println!(
+                "Player {} isn't in the tournament, so the match doesn't count",
+                event.winner
+            );
+            continue;
+        }
+        tournament.bracket.record_winner(event.winner);
+        if tournament.bracket.champion().is_some() {
+            // Nothing left to resume
+            if let Err(error) = fs::remove_file(TOURNAMENT_FILE) {
+                println!("Unable to remove the finished tournament: {}", error);
+            }
+        } else {
+            tournament.bracket.save(TOURNAMENT_FILE);
+        }
+        tournament.show_bracket = true;
+    }
+}
+
+/// Once the game has been reset after a match, show the bracket
+fn tournament_round_reset_system(
+    tournament: Option<ResMut<Tournament>>,
+    mut round_reset_events: EventReader<RoundResetEvent>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(mut tournament) = tournament else {
+        return;
+    };
+    if round_reset_events.iter().next().is_some() && tournament.show_bracket {
+        tournament.show_bracket = false;
+        next_state.set(GameState::Bracket);
+    }
+}
+
+fn spawn_bracket_screen_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    tournament: Option<Res<Tournament>>,
+) {
+    let Some(tournament) = tournament else {
+        return;
+    };
+    let timer = Timer::from_seconds(BRACKET_DISPLAY_DURATION, TimerMode::Once);
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(0.0, 0.0, 0.0, 0.85),
+                custom_size: Some(Vec2::splat(ARENA_RADIUS * 3.0)),
+                ..default()
+            },
+            transform: Transform::from_translation(Vec3::new(0.0, 0.0, LAYER_BRACKET)),
+            ..default()
+        })
+        .insert(BracketScreen {
+            timer: timer.clone(),
+        })
+        .with_children(|parent| {
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(
+                    tournament.bracket.describe(),
+                    TextStyle {
+                        font: asset_server.load("FiraMono-Medium.ttf"),
+                        font_size: 24.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_alignment(TextAlignment::Center),
+                transform: Transform::from_translation(Vec3::new(0.0, 0.0, 0.01)),
+                ..default()
+            });
+        });
+}
+
+/// Keep showing the bracket until someone presses A (or enter), or until it times out
+fn bracket_screen_system(
+    time: Res<Time>,
+    tournament: Option<Res<Tournament>>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut bracket_screen_query: Query<&mut BracketScreen>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(tournament) = tournament else {
+        next_state.set(GameState::Playing);
+        return;
+    };
+    if tournament.bracket.champion().is_some() {
+        // The tournament is over, so leave the results up
+        return;
+    }
+    let pressed = keyboard_input.just_pressed(KeyCode::Return)
+        || gamepads.iter().any(|gamepad| {
+            buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::South))
+        });
+    for mut bracket_screen in bracket_screen_query.iter_mut() {
+        if pressed || bracket_screen.timer.tick(time.delta()).finished() {
+            next_state.set(GameState::Playing);
+        }
+    }
+}
+
+fn despawn_bracket_screen_system(
+    mut commands: Commands,
+    bracket_screen_query: Query<Entity, With<BracketScreen>>,
+) {
+    for entity in bracket_screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roster(count: usize) -> Vec<String> {
+        (0..count)
+            .map(|entrant| format!("Entrant {entrant}"))
+            .collect()
+    }
+
+    /// Play out the whole bracket, with the entrant in `slot` always winning, and return how many
+    /// matches it took
+    fn play_out(bracket: &mut Bracket, slot: usize) -> usize {
+        let mut matches = 0;
+        while bracket.next_pairing().is_some() {
+            // Everyone still in plays or gets a bye, exactly once
+            let round = bracket.rounds.last().unwrap();
+            let mut entrants: Vec<usize> = round
+                .pairings
+                .iter()
+                .flat_map(|pairing| pairing.entrants)
+                .chain(round.byes.iter().copied())
+                .collect();
+            entrants.sort_unstable();
+            if round
+                .pairings
+                .iter()
+                .all(|pairing| pairing.winner.is_none())
+            {
+                assert_eq!(entrants, bracket.active());
+            }
+            bracket.record_winner(slot);
+            matches += 1;
+            assert!(matches < 100, "the bracket never finishes");
+        }
+        matches
+    }
+
+    #[test]
+    fn single_elimination() {
+        for count in 2..=9 {
+            let mut bracket = Bracket::new(roster(count), Elimination::Single);
+            assert_eq!(play_out(&mut bracket, 0), count - 1);
+            let champion = bracket.champion().unwrap();
+            assert_eq!(bracket.losses[champion], 0);
+            assert_eq!(
+                bracket.losses.iter().filter(|&&losses| losses == 1).count(),
+                count - 1
+            );
+        }
+    }
+
+    #[test]
+    fn single_elimination_byes() {
+        let bracket = Bracket::new(roster(5), Elimination::Single);
+        let round = &bracket.rounds[0];
+        assert_eq!(round.pairings.len(), 2);
+        assert_eq!(round.byes.len(), 1);
+    }
+
+    #[test]
+    fn double_elimination() {
+        for count in 2..=9 {
+            for slot in [0, 1] {
+                let mut bracket = Bracket::new(roster(count), Elimination::Double);
+                let matches = play_out(&mut bracket, slot);
+                assert!(
+                    matches == 2 * count - 2 || matches == 2 * count - 1,
+                    "{count} entrants took {matches} matches"
+                );
+                let champion = bracket.champion().unwrap();
+                assert!(bracket.losses[champion] < 2);
+                assert_eq!(
+                    bracket.losses.iter().filter(|&&losses| losses == 2).count(),
+                    count - 1
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn grand_final_reset() {
+        let mut bracket = Bracket::new(roster(2), Elimination::Double);
+        let [first, second] = bracket.next_pairing().unwrap().entrants;
+        // The first entrant wins the opener, then loses the final, so they both have one loss
+        bracket.record_winner(0);
+        let final_pairing = bracket.next_pairing().unwrap();
+        assert_eq!(bracket.champion(), None);
+        let slot = final_pairing
+            .entrants
+            .iter()
+            .position(|&entrant| entrant == second)
+            .unwrap();
+        bracket.record_winner(slot);
+        assert_eq!(bracket.losses[first], 1);
+        assert_eq!(bracket.losses[second], 1);
+        assert_eq!(bracket.champion(), None);
+        // Which means they play again
+        let reset = bracket.next_pairing().unwrap();
+        assert_eq!(bracket.rounds.len(), 3);
+        bracket.record_winner(0);
+        assert_eq!(bracket.champion(), Some(reset.entrants[0]));
+        assert_eq!(bracket.next_pairing().map(|pairing| pairing.entrants), None);
+    }
+
+    #[test]
+    fn record_winner_after_the_end_does_nothing() {
+        let mut bracket = Bracket::new(roster(2), Elimination::Single);
+        bracket.record_winner(1);
+        let champion = bracket.champion();
+        assert!(champion.is_some());
+        bracket.record_winner(0);
+        assert_eq!(bracket.champion(), champion);
+    }
+
+    #[test]
+    fn inconsistent_save_files_are_ignored() {
+        let path = std::env::temp_dir().join("punchball-bracket-test.ron");
+        let mut bracket = Bracket::new(roster(4), Elimination::Single);
+        bracket.save(&path);
+        assert!(Bracket::load(&path).is_some());
+        bracket.losses.pop();
+        bracket.save(&path);
+        assert!(Bracket::load(&path).is_none());
+        let mut bracket = Bracket::new(roster(4), Elimination::Single);
+        bracket.rounds[0].pairings[0].entrants[1] = 4;
+        bracket.save(&path);
+        assert!(Bracket::load(&path).is_none());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn empty_names_are_rejected() {
+        let names = vec!["Ann".to_string(), String::new(), "Bob".to_string()];
+        assert!(Tournament::new(names, Elimination::Single).is_none());
+    }
+}